@@ -0,0 +1,950 @@
+use iced::widget::{
+    button, column, container, image, mouse_area, pick_list, row, scrollable, slider, space, stack,
+    text, text_input, toggler, Column, Row,
+};
+use iced::{
+    event, mouse, time, window, Alignment, Border, Color, ContentFit, Element, Font, Length,
+    Padding, Point, Subscription, Task, Theme,
+};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use tiny2::capture::{Capture, PreviewHandle, Resolution};
+use tiny2::explore::{self, Explorer};
+use tiny2::motion::{MotionHandle, Move};
+use tiny2::settings::{self, Kind, Setting, Value, SETTINGS};
+use tiny2::{
+    remote::default_socket_path, Angle, Camera, CtrlRange, Error, FieldOfView, FocusMode,
+    OBSBotWebCam, PresetStore, Profile, RemoteCamera, SoftLimits, Trace,
+};
+
+/// Set from --debug flag at startup; controls debug UI and verbose logging.
+static mut DEBUG: bool = false;
+
+fn debug_mode() -> bool {
+    // SAFETY: only written once in main() before iced starts.
+    unsafe { DEBUG }
+}
+
+/// Command-line options other than --debug, set once in main().
+#[derive(Debug, Default)]
+struct Options {
+    /// Daemon socket from --remote[=SOCKET], if the camera should be reached
+    /// through tiny2d instead of opened directly.
+    remote: Option<PathBuf>,
+    /// JSONL file from --trace FILE to record camera traffic to.
+    trace: Option<PathBuf>,
+    /// Whether --preview asked for the live preview at startup.
+    preview: bool,
+}
+
+static OPTIONS: OnceLock<Options> = OnceLock::new();
+
+fn parse_options() -> Options {
+    let mut options = Options::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if let Some(socket) = arg.strip_prefix("--remote") {
+            options.remote = match socket.strip_prefix('=') {
+                Some(socket) => Some(socket.into()),
+                None if socket.is_empty() => Some(default_socket_path()),
+                None => continue,
+            };
+        } else if let Some(path) = arg.strip_prefix("--trace=") {
+            options.trace = Some(path.into());
+        } else if arg == "--trace" {
+            options.trace = args.next().map(PathBuf::from);
+        } else if arg == "--preview" {
+            options.preview = true;
+        }
+    }
+    options
+}
+
+/// Speed of the press-and-hold PTZ buttons, as a fraction of full speed.
+const PTZ_SPEED: f64 = 0.5;
+
+/// A press-and-hold PTZ button: an axis and a signed speed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PtzAction {
+    Pan(f64),
+    Tilt(f64),
+    Zoom(f64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Message {
+    /// Set the named setting from `tiny2::settings`.
+    Set(&'static str, Value),
+    ProfilePath(String),
+    SaveProfile,
+    LoadProfile,
+    TextInput(String),
+    TextInput02(String),
+    SendCommand,
+    SendCommand02,
+    HexDump,
+    HexDump02,
+    ExplorePath(String),
+    ExportSession,
+    DismissError,
+    // PTZ press-and-hold
+    StartMove(PtzAction),
+    StopMove,
+    Tick,
+    /// Move smoothly to the named preset.
+    RecallPreset(String),
+    /// The cursor moved over the frame area, or left it.
+    FrameCursor(Option<Point>),
+    /// Center the camera on the point under the cursor in the frame area.
+    CenterOn,
+    /// Turn the live preview on or off.
+    TogglePreview(bool),
+    /// Show the newest preview frame.
+    PreviewTick,
+    SnapshotPath(String),
+    SnapshotSize(Resolution),
+    /// Save a frame to the snapshot path.
+    Snapshot,
+}
+
+struct MainPanel {
+    camera: Arc<dyn OBSBotWebCam + Send + Sync>,
+    /// Last known value of each setting, by name. Settings that can't be
+    /// read back are filled in once they have been set.
+    values: BTreeMap<&'static str, Value>,
+    focus_range: Option<CtrlRange>,
+    profile_path: String,
+    text_input: String,
+    text_input_02: String,
+    explorer: Explorer,
+    explore_path: String,
+    error_message: Option<String>,
+    /// Ranges of the PTZ axes the camera has.
+    pan: Option<CtrlRange>,
+    tilt: Option<CtrlRange>,
+    zoom: Option<CtrlRange>,
+    limits: SoftLimits,
+    /// Effective field of view from the FOV mode and zoom.
+    fov: Option<FieldOfView>,
+    held_action: Option<PtzAction>,
+    presets: PresetStore,
+    /// The smooth move in progress, if any.
+    motion: Option<MotionHandle>,
+    /// Where the cursor is over the frame area.
+    frame_cursor: Option<Point>,
+    /// What to open the capture node by: the control node's bus info, or
+    /// the camera's name when it is reached through tiny2d.
+    capture_hint: String,
+    preview: Option<PreviewHandle>,
+    preview_frame: Option<image::Handle>,
+    snapshot_path: String,
+    snapshot_size: Resolution,
+}
+
+impl MainPanel {
+    /// Send a raw hex command through the protocol explorer.
+    fn explore(&mut self, selector: u8, hex: &str) {
+        let command = match hex::decode(hex) {
+            Ok(c) => c,
+            Err(e) => {
+                self.error_message = Some(format!("Invalid hex string: {}", e));
+                return;
+            }
+        };
+        match self.explorer.send(&*self.camera, selector, &command) {
+            Ok(step) => {
+                if let Some(e) = &step.error {
+                    self.error_message = Some(format!("Failed to send command: {}", e));
+                }
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Failed to read status: {}", e));
+            }
+        }
+    }
+
+    /// Re-read the current settings from the camera. Returns false if the
+    /// status block couldn't be read.
+    fn refresh(&mut self) -> bool {
+        if let Err(e) = self.camera.get_status() {
+            self.error_message = Some(format!("Failed to get camera status: {}", e));
+            return false;
+        }
+        for setting in &SETTINGS {
+            match setting.get(&*self.camera) {
+                Ok(Some(value)) => {
+                    self.values.insert(setting.name, value);
+                }
+                Ok(None) => {}
+                Err(_) => {
+                    self.values.remove(setting.name);
+                }
+            }
+        }
+        true
+    }
+
+    /// Re-read the pan, tilt and zoom positions while the camera moves,
+    /// and the field of view that goes with them.
+    fn refresh_position(&mut self) {
+        for name in ["pan", "tilt", "zoom"] {
+            let setting = settings::lookup(name).expect("PTZ settings are in the registry");
+            if let Ok(Some(value)) = setting.get(&*self.camera) {
+                self.values.insert(name, value);
+            }
+        }
+        self.fov = self.camera.get_field_of_view().ok();
+    }
+
+    fn start_ptz(&mut self, action: PtzAction) {
+        let (pan, tilt, zoom) = match action {
+            PtzAction::Pan(speed) => (speed, 0.0, 0.0),
+            PtzAction::Tilt(speed) => (0.0, speed, 0.0),
+            PtzAction::Zoom(speed) => (0.0, 0.0, speed),
+        };
+        if let Err(e) = self.camera.move_continuous(pan, tilt, zoom) {
+            self.error_message = Some(format!("PTZ error: {}", e));
+        }
+    }
+
+    fn stop_ptz(&mut self) {
+        if let Err(e) = self.camera.stop() {
+            self.error_message = Some(format!("PTZ error: {}", e));
+        }
+    }
+
+    fn start_preview(&mut self) {
+        self.preview = Some(Capture::spawn(
+            &self.capture_hint,
+            PREVIEW_WIDTH,
+            PREVIEW_HEIGHT,
+        ));
+    }
+
+    fn stop_preview(&mut self) {
+        self.preview = None;
+        self.preview_frame = None;
+    }
+}
+
+fn boot() -> (MainPanel, Task<Message>) {
+    let options = OPTIONS.get_or_init(Options::default);
+    let mut capture_hint = "OBSBOT Tiny 2".to_string();
+    let camera: Arc<dyn OBSBotWebCam + Send + Sync> = match &options.remote {
+        Some(socket) => Arc::new(RemoteCamera::wait_for(
+            socket,
+            "OBSBOT Tiny 2",
+            Duration::from_secs(1),
+        )),
+        None => {
+            let mut camera = Camera::wait_for("OBSBOT Tiny 2", Duration::from_secs(1));
+            camera.set_verbose(debug_mode());
+            match options.trace.as_ref().map(Trace::create) {
+                Some(Ok(trace)) => camera = camera.with_trace(trace),
+                Some(Err(e)) => eprintln!("Failed to create trace file: {}", e),
+                None => {}
+            }
+            if let Some(device) = camera.device() {
+                capture_hint = device.bus_info.clone();
+            }
+            Arc::new(camera)
+        }
+    };
+    let presets = PresetStore::load(PresetStore::default_path()).unwrap_or_else(|e| {
+        eprintln!("Failed to load presets: {}", e);
+        PresetStore::default()
+    });
+
+    let mut panel = MainPanel {
+        camera,
+        values: BTreeMap::new(),
+        focus_range: None,
+        profile_path: Profile::default_path().display().to_string(),
+        text_input: String::new(),
+        text_input_02: String::new(),
+        explorer: Explorer::new(),
+        explore_path: "explore.json".to_string(),
+        error_message: None,
+        pan: None,
+        tilt: None,
+        zoom: None,
+        limits: SoftLimits::default(),
+        fov: None,
+        held_action: None,
+        presets,
+        motion: None,
+        frame_cursor: None,
+        capture_hint,
+        preview: None,
+        preview_frame: None,
+        snapshot_path: "snapshot.jpg".to_string(),
+        snapshot_size: Resolution::new(1920, 1080),
+    };
+    if options.preview {
+        panel.start_preview();
+    }
+
+    if !panel.refresh() {
+        return (panel, Task::none());
+    }
+
+    panel.pan = panel.camera.query_pan_range().ok();
+    panel.tilt = panel.camera.query_tilt_range().ok();
+    panel.zoom = panel.camera.query_zoom_range().ok();
+    match panel.camera.get_soft_limits() {
+        Ok(limits) => panel.limits = limits,
+        Err(e) => panel.error_message = Some(format!("Failed to get soft limits: {}", e)),
+    }
+    panel.focus_range = panel.camera.query_focus_range().ok();
+    panel.refresh_position();
+
+    (panel, Task::none())
+}
+
+fn update(state: &mut MainPanel, message: Message) -> Task<Message> {
+    match message {
+        Message::Set(name, value) => {
+            let setting = settings::lookup(name).expect("GUI settings come from the registry");
+            match setting.set(&*state.camera, value) {
+                Ok(()) => {
+                    state.values.insert(name, value);
+                    if name == "fov" || name == "zoom" {
+                        state.refresh_position();
+                    }
+                }
+                Err(e) => {
+                    state.error_message = Some(format!(
+                        "Failed to change {}: {}",
+                        setting.label.to_lowercase(),
+                        e
+                    ));
+                }
+            }
+        }
+        Message::ProfilePath(s) => {
+            state.profile_path = s;
+        }
+        Message::SaveProfile => {
+            let result = Profile::capture(&*state.camera)
+                .and_then(|profile| profile.save(&state.profile_path));
+            if let Err(e) = result {
+                state.error_message = Some(format!("Failed to save profile: {}", e));
+            }
+        }
+        Message::LoadProfile => match Profile::load(&state.profile_path) {
+            Ok(profile) => {
+                let report = profile.apply(&*state.camera);
+                if !report.is_ok() {
+                    let failed: Vec<String> = report
+                        .failed
+                        .iter()
+                        .map(|(field, e)| format!("{}: {}", field, e))
+                        .collect();
+                    state.error_message =
+                        Some(format!("Failed to apply profile ({})", failed.join(", ")));
+                }
+                state.refresh();
+            }
+            Err(e) => {
+                state.error_message = Some(format!("Failed to load profile: {}", e));
+            }
+        },
+        Message::TextInput(s) => {
+            state.text_input = s;
+        }
+        Message::TextInput02(s) => {
+            state.text_input_02 = s;
+        }
+        Message::SendCommand => state.explore(0x6, &state.text_input.clone()),
+        Message::SendCommand02 => state.explore(0x2, &state.text_input_02.clone()),
+        Message::HexDump => match state.camera.read_selector(0x6) {
+            Ok(data) => hexdump::hexdump(&data),
+            Err(e) => {
+                state.error_message = Some(format!("Failed to dump: {}", e));
+            }
+        },
+        Message::HexDump02 => match state.camera.read_selector(0x2) {
+            Ok(data) => hexdump::hexdump(&data),
+            Err(e) => {
+                state.error_message = Some(format!("Failed to dump: {}", e));
+            }
+        },
+        Message::ExplorePath(s) => {
+            state.explore_path = s;
+        }
+        Message::ExportSession => {
+            if let Err(e) = state.explorer.save(&state.explore_path) {
+                state.error_message = Some(format!("Failed to export session: {}", e));
+            }
+        }
+        Message::DismissError => {
+            state.error_message = None;
+        }
+        Message::StartMove(action) => {
+            state.motion = None;
+            state.held_action = Some(action);
+            state.start_ptz(action);
+        }
+        Message::StopMove => {
+            // Any mouse release ends up here, not only those over a button.
+            if state.held_action.take().is_some() {
+                state.stop_ptz();
+                state.refresh_position();
+            }
+        }
+        Message::Tick => {
+            state.refresh_position();
+            if state.motion.as_ref().is_some_and(MotionHandle::is_finished) {
+                let motion = state.motion.take().expect("checked above");
+                if let Err(e) = motion.join() {
+                    state.error_message = Some(format!("Failed to move: {}", e));
+                }
+            }
+        }
+        Message::RecallPreset(name) => match state.presets.get(&name).copied() {
+            Ok(preset) => {
+                if state.held_action.take().is_some() {
+                    state.stop_ptz();
+                }
+                let motion = Move::to(preset.into()).spawn(state.camera.clone());
+                state.motion = Some(motion);
+            }
+            Err(e) => {
+                state.error_message = Some(format!("Failed to recall preset: {}", e));
+            }
+        },
+        Message::FrameCursor(position) => {
+            state.frame_cursor = position;
+        }
+        Message::CenterOn => {
+            if let Some(p) = state.frame_cursor {
+                if state.held_action.take().is_some() {
+                    state.stop_ptz();
+                }
+                state.motion = None;
+                let (x, y) = (p.x as f64, p.y as f64);
+                let (w, h) = (FRAME_WIDTH as f64, FRAME_HEIGHT as f64);
+                if let Err(e) = state.camera.center_on(x, y, w, h) {
+                    state.error_message = Some(format!("Failed to center: {}", e));
+                }
+                state.refresh_position();
+            }
+        }
+        Message::TogglePreview(on) => {
+            if on {
+                state.start_preview();
+            } else {
+                state.stop_preview();
+            }
+        }
+        Message::PreviewTick => {
+            let Some(preview) = &state.preview else {
+                return Task::none();
+            };
+            if let Some(frame) = preview.take_frame() {
+                let (width, height) = frame.dimensions();
+                state.preview_frame =
+                    Some(image::Handle::from_rgba(width, height, frame.into_raw()));
+            }
+            if preview.is_finished() {
+                let preview = state.preview.take().expect("checked above");
+                state.preview_frame = None;
+                state.error_message = match preview.join() {
+                    Ok(()) => None,
+                    Err(Error::StreamBusy) => Some(
+                        "Preview unavailable: another application is using the camera".to_string(),
+                    ),
+                    Err(e) => Some(format!("Preview stopped: {}", e)),
+                };
+            }
+        }
+        Message::SnapshotPath(s) => {
+            state.snapshot_path = s;
+        }
+        Message::SnapshotSize(size) => {
+            state.snapshot_size = size;
+        }
+        Message::Snapshot => {
+            // Only one stream at a time, so the preview has to let go of the
+            // camera first.
+            let preview = state.preview.take();
+            if let Some(preview) = preview.as_ref() {
+                preview.stop();
+            }
+            let previewing = preview.is_some_and(|p| p.join().is_ok());
+            let path = Path::new(&state.snapshot_path);
+            if let Err(e) = Capture::snapshot(&state.capture_hint, state.snapshot_size, path) {
+                state.error_message = Some(format!("Failed to save snapshot: {}", e));
+            }
+            if previewing {
+                state.start_preview();
+            } else {
+                state.preview_frame = None;
+            }
+        }
+    }
+    Task::none()
+}
+
+/// Size to ask the camera for when previewing; small enough to decode
+/// every frame.
+const PREVIEW_WIDTH: u32 = 640;
+const PREVIEW_HEIGHT: u32 = 360;
+
+const FRAME_WIDTH: f32 = 320.0;
+const FRAME_HEIGHT: f32 = 180.0;
+
+/// The live preview, or a stand-in for it, with cross-hairs at its centre.
+/// Clicking a point in it turns the camera to center on that point.
+fn frame_area(state: &MainPanel) -> Element<'_, Message> {
+    const LINE: f32 = 1.0;
+    let line = |padding: Padding| {
+        let area = container(space())
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(|theme: &Theme| container::Style {
+                background: Some(theme.extended_palette().background.base.text.into()),
+                ..Default::default()
+            });
+        container(area)
+            .padding(padding)
+            .width(FRAME_WIDTH)
+            .height(FRAME_HEIGHT)
+    };
+    // Stretched to fill the area, so that clicks map onto the whole frame.
+    let background: Element<'_, Message> = match &state.preview_frame {
+        Some(frame) => image(frame.clone())
+            .width(FRAME_WIDTH)
+            .height(FRAME_HEIGHT)
+            .content_fit(ContentFit::Fill)
+            .into(),
+        None => container(space())
+            .width(FRAME_WIDTH)
+            .height(FRAME_HEIGHT)
+            .style(|theme: &Theme| container::Style {
+                background: Some(theme.extended_palette().background.strong.color.into()),
+                ..Default::default()
+            })
+            .into(),
+    };
+    let layers = stack![
+        background,
+        line(Padding {
+            top: (FRAME_HEIGHT - LINE) / 2.0,
+            bottom: (FRAME_HEIGHT - LINE) / 2.0,
+            ..Padding::ZERO
+        }),
+        line(Padding {
+            left: (FRAME_WIDTH - LINE) / 2.0,
+            right: (FRAME_WIDTH - LINE) / 2.0,
+            ..Padding::ZERO
+        }),
+    ];
+    let area = mouse_area(layers)
+        .on_move(|p| Message::FrameCursor(Some(p)))
+        .on_exit(Message::FrameCursor(None))
+        .on_press(Message::CenterOn)
+        .interaction(mouse::Interaction::Crosshair);
+    container(area).center_x(Length::Fill).into()
+}
+
+/// A map of the pan/tilt range: the part the soft limits allow, shaded, and
+/// the current position as a dot. Pan increases to the right and tilt
+/// upwards.
+fn ptz_region<'a>(state: &MainPanel, pan: CtrlRange, tilt: CtrlRange) -> Element<'a, Message> {
+    const WIDTH: f32 = 300.0;
+    const DOT: f32 = 8.0;
+    let pan_span = (pan.maximum - pan.minimum).max(1) as f32;
+    let tilt_span = (tilt.maximum - tilt.minimum).max(1) as f32;
+    let height = WIDTH * tilt_span / pan_span;
+    let x = |v: i32| ((v - pan.minimum) as f32 / pan_span * WIDTH).clamp(0.0, WIDTH);
+    let y = |v: i32| ((tilt.maximum - v) as f32 / tilt_span * height).clamp(0.0, height);
+    // A rectangle of `color`, inset from the map's edges by `padding`.
+    let fill = |color: fn(&Theme) -> Color, padding: Padding| {
+        let area = container(space())
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(move |theme: &Theme| container::Style {
+                background: Some(color(theme).into()),
+                ..Default::default()
+            });
+        container(area).padding(padding).width(WIDTH).height(height)
+    };
+
+    let allowed_pan = state.limits.pan.restrict(&pan);
+    let allowed_tilt = state.limits.tilt.restrict(&tilt);
+    let mut layers = stack![
+        fill(
+            |t| t.extended_palette().background.strong.color,
+            Padding::ZERO
+        ),
+        fill(
+            |t| t.extended_palette().primary.weak.color,
+            Padding {
+                top: y(allowed_tilt.maximum),
+                right: WIDTH - x(allowed_pan.maximum),
+                bottom: height - y(allowed_tilt.minimum),
+                left: x(allowed_pan.minimum),
+            },
+        ),
+    ];
+    if let (Some(Value::Int(p)), Some(Value::Int(t))) =
+        (state.values.get("pan"), state.values.get("tilt"))
+    {
+        let (px, py) = (x(*p), y(*t));
+        layers = layers.push(fill(
+            |t| t.extended_palette().danger.base.color,
+            Padding {
+                top: (py - DOT / 2.0).clamp(0.0, height - DOT),
+                right: WIDTH - (px + DOT / 2.0).clamp(DOT, WIDTH),
+                bottom: height - (py + DOT / 2.0).clamp(DOT, height),
+                left: (px - DOT / 2.0).clamp(0.0, WIDTH - DOT),
+            },
+        ));
+    }
+    container(layers).center_x(Length::Fill).into()
+}
+
+/// The soft limits in words, for under the PTZ map.
+fn limits_summary(limits: &SoftLimits) -> String {
+    let end = |v: Option<i32>, angle: bool| match v {
+        Some(v) if angle => Angle::from_arcsec(v).to_string(),
+        Some(v) => v.to_string(),
+        None => "any".to_string(),
+    };
+    let axes = [
+        ("pan", limits.pan, true),
+        ("tilt", limits.tilt, true),
+        ("zoom", limits.zoom, false),
+    ];
+    let parts: Vec<String> = axes
+        .iter()
+        .filter(|(_, limit, _)| !limit.is_unbounded())
+        .map(|(name, limit, angle)| {
+            format!(
+                "{} {} to {}",
+                name,
+                end(limit.min, *angle),
+                end(limit.max, *angle)
+            )
+        })
+        .collect();
+    format!("Soft limits: {}", parts.join(", "))
+}
+
+/// A labelled row of buttons, or rows of two for long lists, for a choice
+/// setting. The current value is highlighted.
+fn choice_buttons<'a>(setting: &'static Setting, current: Option<&Value>) -> Element<'a, Message> {
+    let buttons: Vec<Element<'a, Message>> = setting
+        .choices()
+        .into_iter()
+        .enumerate()
+        .map(|(i, label)| {
+            let value = Value::Choice(i);
+            let style = if current == Some(&value) {
+                button::primary
+            } else {
+                button::secondary
+            };
+            button(text(label).align_x(Alignment::Center))
+                .on_press(Message::Set(setting.name, value))
+                .style(style)
+                .width(Length::Fill)
+                .into()
+        })
+        .collect();
+    let per_row = if buttons.len() > 4 { 2 } else { buttons.len() };
+    let mut rows = Column::new().spacing(10).push(text(setting.label).size(14));
+    let mut buttons = buttons.into_iter().peekable();
+    while buttons.peek().is_some() {
+        rows = rows.push(Row::with_children(buttons.by_ref().take(per_row)).spacing(10));
+    }
+    rows.into()
+}
+
+fn view(state: &MainPanel) -> Element<'_, Message> {
+    let mut c = Column::new()
+        .width(Length::Fill)
+        .align_x(Alignment::Center)
+        .spacing(10)
+        .padding(10);
+
+    // Choices and toggles come from the settings registry, with runs of
+    // toggles sharing a row. Ranges (focus and PTZ) get their own controls.
+    let mut toggles = Vec::new();
+    for setting in &SETTINGS {
+        if setting.kind == Kind::Toggle {
+            let on = state.values.get(setting.name) == Some(&Value::Bool(true));
+            toggles.push(
+                toggler(on)
+                    .label(setting.label)
+                    .on_toggle(move |on| Message::Set(setting.name, Value::Bool(on)))
+                    .into(),
+            );
+            continue;
+        }
+        if !toggles.is_empty() {
+            c = c.push(Row::from_vec(std::mem::take(&mut toggles)).spacing(10));
+        }
+        if setting.kind == Kind::Choice {
+            c = c.push(choice_buttons(setting, state.values.get(setting.name)));
+        }
+    }
+    if !toggles.is_empty() {
+        c = c.push(Row::from_vec(toggles).spacing(10));
+    }
+
+    let manual_focus = state.values.get("focus-mode") == Some(&Value::from(FocusMode::Manual));
+    if let (Some(range), true) = (state.focus_range, manual_focus) {
+        let focus = match state.values.get("focus") {
+            Some(&Value::Int(v)) => v,
+            _ => range.default_value,
+        };
+        c = c.push(
+            slider(range.minimum..=range.maximum, focus, |v| {
+                Message::Set("focus", Value::Int(v))
+            })
+            .step(range.step.max(1)),
+        );
+    }
+
+    c = c.push(
+        row![
+            text_input("Profile path", &state.profile_path)
+                .on_input(Message::ProfilePath)
+                .width(Length::Fill),
+            button("Save profile").on_press(Message::SaveProfile),
+            button("Load profile").on_press(Message::LoadProfile),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+    );
+    c = c.push(
+        row![
+            text_input("Snapshot path", &state.snapshot_path)
+                .on_input(Message::SnapshotPath)
+                .width(Length::Fill),
+            pick_list(
+                Resolution::COMMON,
+                Some(state.snapshot_size),
+                Message::SnapshotSize
+            ),
+            button("Snapshot").on_press(Message::Snapshot),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center),
+    );
+
+    if let Some(err) = &state.error_message {
+        c = c.push(
+            row![
+                text(err).width(Length::Fill),
+                button("Dismiss").on_press(Message::DismissError),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+        );
+    }
+
+    // Pan/Tilt/Zoom press-and-hold controls
+    if state.pan.is_some() || state.tilt.is_some() || state.zoom.is_some() {
+        let ptz_btn = |label: &'static str| {
+            container(text(label).align_x(Alignment::Center))
+                .padding([4, 12])
+                .style(|theme: &Theme| {
+                    let palette = theme.extended_palette();
+                    container::Style {
+                        background: Some(palette.primary.weak.color.into()),
+                        text_color: Some(palette.primary.weak.text),
+                        border: Border {
+                            radius: 4.0.into(),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    }
+                })
+        };
+
+        let mut ptz_row = row![].spacing(10).align_y(Alignment::Center);
+
+        if state.pan.is_some() {
+            ptz_row = ptz_row.push(
+                row![
+                    mouse_area(ptz_btn("<"))
+                        .on_press(Message::StartMove(PtzAction::Pan(-PTZ_SPEED)))
+                        .on_release(Message::StopMove),
+                    mouse_area(ptz_btn(">"))
+                        .on_press(Message::StartMove(PtzAction::Pan(PTZ_SPEED)))
+                        .on_release(Message::StopMove),
+                ]
+                .spacing(5),
+            );
+        }
+
+        if state.tilt.is_some() {
+            ptz_row = ptz_row.push(
+                row![
+                    mouse_area(ptz_btn("v"))
+                        .on_press(Message::StartMove(PtzAction::Tilt(-PTZ_SPEED)))
+                        .on_release(Message::StopMove),
+                    mouse_area(ptz_btn("^"))
+                        .on_press(Message::StartMove(PtzAction::Tilt(PTZ_SPEED)))
+                        .on_release(Message::StopMove),
+                ]
+                .spacing(5),
+            );
+        }
+
+        if state.zoom.is_some() {
+            ptz_row = ptz_row.push(
+                row![
+                    mouse_area(ptz_btn("-"))
+                        .on_press(Message::StartMove(PtzAction::Zoom(-PTZ_SPEED)))
+                        .on_release(Message::StopMove),
+                    mouse_area(ptz_btn("+"))
+                        .on_press(Message::StartMove(PtzAction::Zoom(PTZ_SPEED)))
+                        .on_release(Message::StopMove),
+                ]
+                .spacing(5),
+            );
+        }
+
+        c = c.push(ptz_row);
+
+        if let (Some(pan), Some(tilt)) = (state.pan, state.tilt) {
+            c = c.push(
+                toggler(state.preview.is_some())
+                    .label("Live preview")
+                    .on_toggle(Message::TogglePreview),
+            );
+            c = c.push(frame_area(state));
+            c = c.push(ptz_region(state, pan, tilt));
+        }
+        if state.limits != SoftLimits::default() {
+            c = c.push(text(limits_summary(&state.limits)).size(12));
+        }
+        if let Some(fov) = state.fov {
+            c = c.push(
+                text(format!(
+                    "Field of view {:.1}° × {:.1}°",
+                    fov.horizontal, fov.vertical
+                ))
+                .size(12),
+            );
+        }
+
+        if !state.presets.presets.is_empty() {
+            let names: Vec<String> = state.presets.presets.keys().cloned().collect();
+            c = c.push(
+                pick_list(names, None::<String>, Message::RecallPreset).placeholder("Go to preset"),
+            );
+        }
+    } else {
+        c = c.push(text("PTZ controls not available for this device"));
+    }
+
+    if debug_mode() {
+        c = c.push(
+            column![
+                text_input("0x06 hex string", &state.text_input)
+                    .on_input(Message::TextInput)
+                    .on_submit(Message::SendCommand),
+                text_input("0x02 hex string", &state.text_input_02)
+                    .on_input(Message::TextInput02)
+                    .on_submit(Message::SendCommand02),
+                button("Dump 0x06")
+                    .on_press(Message::HexDump)
+                    .width(Length::Fill),
+                button("Dump 0x02")
+                    .on_press(Message::HexDump02)
+                    .width(Length::Fill),
+            ]
+            .spacing(10),
+        );
+
+        if let Some(step) = state.explorer.history.last() {
+            let changes = step.changes();
+            let mut report = format!(
+                "#{} 0x{:02x} {}: {} change(s)\n",
+                state.explorer.history.len(),
+                step.selector,
+                hex::encode(&step.command),
+                changes.len()
+            );
+            for buffer in &step.after {
+                if changes.iter().any(|c| c.selector == buffer.selector) {
+                    report += &format!("selector 0x{:02x}:\n", buffer.selector);
+                    report += &explore::hexdump(buffer, &changes);
+                }
+            }
+            for change in &changes {
+                report += &format!("{}\n", change);
+            }
+            c = c.push(
+                scrollable(text(report).font(Font::MONOSPACE).size(12))
+                    .height(Length::Fixed(150.0))
+                    .width(Length::Fill),
+            );
+        }
+        c = c.push(
+            row![
+                text_input("Session file", &state.explore_path)
+                    .on_input(Message::ExplorePath)
+                    .on_submit(Message::ExportSession),
+                button("Export session").on_press(Message::ExportSession),
+            ]
+            .spacing(10),
+        );
+    }
+
+    scrollable(c).into()
+}
+
+fn stop_on_mouse_release(
+    event: iced::Event,
+    _status: event::Status,
+    _id: window::Id,
+) -> Option<Message> {
+    if let iced::Event::Mouse(iced::mouse::Event::ButtonReleased(iced::mouse::Button::Left)) = event
+    {
+        Some(Message::StopMove)
+    } else {
+        None
+    }
+}
+
+fn subscription(state: &MainPanel) -> Subscription<Message> {
+    let tick = if state.held_action.is_some() || state.motion.is_some() {
+        time::every(Duration::from_millis(150)).map(|_| Message::Tick)
+    } else {
+        Subscription::none()
+    };
+    let preview = if state.preview.is_some() {
+        time::every(Duration::from_millis(66)).map(|_| Message::PreviewTick)
+    } else {
+        Subscription::none()
+    };
+    Subscription::batch(vec![
+        tick,
+        preview,
+        event::listen_with(stop_on_mouse_release),
+    ])
+}
+
+fn main() -> iced::Result {
+    // SAFETY: written once here before any other threads start.
+    unsafe {
+        DEBUG = std::env::args().any(|a| a == "--debug");
+    }
+    let _ = OPTIONS.set(parse_options());
+
+    let window_height = if debug_mode() { 1200.0 } else { 850.0 };
+
+    iced::application(boot, update, view)
+        .subscription(subscription)
+        .window_size((400.0, window_height))
+        .run()
+}
@@ -0,0 +1,202 @@
+use rosc::{OscMessage, OscPacket, OscType};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::net::UdpSocket;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tiny2::motion::{MotionHandle, Move, PtzPosition};
+use tiny2::settings::{self, Value};
+use tiny2::{remote::default_socket_path, Camera, Error, OBSBotWebCam, RemoteCamera, Trace};
+
+#[derive(Error, Debug)]
+enum ServerError {
+    #[error(transparent)]
+    Camera(#[from] tiny2::Error),
+    #[error("IO error: {0}")]
+    IOError(#[from] std::io::Error),
+    #[error("argument {0} is missing or isn't {1}")]
+    BadArgument(usize, &'static str),
+}
+
+struct OBSBotOSCServer {
+    addr: String,
+    cameras: Vec<Arc<dyn OBSBotWebCam + Send + Sync>>,
+    /// Smooth moves in progress, by camera index.
+    motions: RefCell<BTreeMap<usize, MotionHandle>>,
+}
+
+impl OBSBotOSCServer {
+    pub fn run_server(&self) -> Result<(), ServerError> {
+        let socket = UdpSocket::bind(&self.addr)?;
+
+        let mut buf = [0; 1024];
+        loop {
+            let (amt, src) = socket.recv_from(&mut buf)?;
+
+            match rosc::decoder::decode_udp(&buf[..amt]) {
+                Ok((_, packet)) => self.handle_packet(packet),
+                Err(e) => eprintln!("{}: bad OSC packet: {}", src, e),
+            }
+        }
+    }
+
+    /// Handle every message in a packet. Messages that fail, e.g. with a
+    /// value out of range or past a soft limit, are logged and skipped so
+    /// that one bad value doesn't stop the server.
+    fn handle_packet(&self, packet: OscPacket) {
+        match packet {
+            OscPacket::Message(m) => {
+                let addr = m.addr.clone();
+                if let Err(e) = self.handle_message(m) {
+                    eprintln!("{}: {}", addr, e);
+                }
+            }
+            OscPacket::Bundle(bundle) => {
+                for packet in bundle.content {
+                    self.handle_packet(packet);
+                }
+            }
+        }
+    }
+
+    pub fn handle_message(&self, msg: OscMessage) -> Result<(), ServerError> {
+        // Settings: <address> <camera> <int>, with addresses from the
+        // registry, e.g. /OBSBOT/WebCam/Tiny/SetAiMode or /pan.
+        if let Some(setting) = settings::osc_route(&msg.addr) {
+            let (_, camera) = self.get_camera(&msg, 0)?;
+            let value = Value::Int(Self::get_int_arg(&msg, 1)?);
+            return Ok(setting.set(&**camera, value)?);
+        }
+        match msg.addr.as_str() {
+            // PTZ relative: /pan/relative <camera> <delta>, etc.
+            "/pan/relative" => {
+                let (_, camera) = self.get_camera(&msg, 0)?;
+                Ok(camera.pan_relative(Self::get_int_arg(&msg, 1)?)?)
+            }
+            "/tilt/relative" => {
+                let (_, camera) = self.get_camera(&msg, 0)?;
+                Ok(camera.tilt_relative(Self::get_int_arg(&msg, 1)?)?)
+            }
+            "/zoom/relative" => {
+                let (_, camera) = self.get_camera(&msg, 0)?;
+                Ok(camera.zoom_relative(Self::get_int_arg(&msg, 1)?)?)
+            }
+            // Smooth move: /ptz/move <camera> <pan> <tilt> <zoom> <duration_ms>.
+            // Replaces any move already running on that camera.
+            "/ptz/move" => {
+                let (index, camera) = self.get_camera(&msg, 0)?;
+                let target = PtzPosition {
+                    pan: Some(Self::get_int_arg(&msg, 1)?),
+                    tilt: Some(Self::get_int_arg(&msg, 2)?),
+                    zoom: Some(Self::get_int_arg(&msg, 3)?),
+                };
+                let duration = Duration::from_millis(Self::get_int_arg(&msg, 4)?.max(0) as u64);
+                let motion = Move::to(target).over(duration).spawn(camera.clone());
+                self.motions.borrow_mut().insert(index, motion);
+                Ok(())
+            }
+            // Continuous move: /ptz/continuous <camera> <pan> <tilt> <zoom>,
+            // with speeds from -1 to 1 as floats or ints.
+            "/ptz/continuous" => {
+                let (index, camera) = self.get_camera(&msg, 0)?;
+                let (pan, tilt, zoom) = (
+                    Self::get_float_arg(&msg, 1)?,
+                    Self::get_float_arg(&msg, 2)?,
+                    Self::get_float_arg(&msg, 3)?,
+                );
+                self.motions.borrow_mut().remove(&index);
+                Ok(camera.move_continuous(pan, tilt, zoom)?)
+            }
+            // /ptz/stop <camera>: ends smooth and continuous moves.
+            "/ptz/stop" => {
+                let (index, camera) = self.get_camera(&msg, 0)?;
+                self.motions.borrow_mut().remove(&index);
+                Ok(camera.stop()?)
+            }
+            _ => {
+                println!("{:?}", msg);
+                Ok(())
+            }
+        }
+    }
+
+    /// The camera whose index is argument `arg_idx`, and that index.
+    fn get_camera(
+        &self,
+        msg: &OscMessage,
+        arg_idx: usize,
+    ) -> Result<(usize, &Arc<dyn OBSBotWebCam + Send + Sync>), ServerError> {
+        let index = Self::get_int_arg(msg, arg_idx)?;
+        usize::try_from(index)
+            .ok()
+            .and_then(|i| Some((i, self.cameras.get(i)?)))
+            .ok_or(ServerError::Camera(Error::NoCameraFound))
+    }
+
+    fn get_int_arg(msg: &OscMessage, arg_idx: usize) -> Result<i32, ServerError> {
+        match msg.args.get(arg_idx) {
+            Some(OscType::Int(x)) => Ok(*x),
+            _ => Err(ServerError::BadArgument(arg_idx, "an int")),
+        }
+    }
+
+    fn get_float_arg(msg: &OscMessage, arg_idx: usize) -> Result<f64, ServerError> {
+        match msg.args.get(arg_idx) {
+            Some(OscType::Float(x)) => Ok(*x as f64),
+            Some(OscType::Double(x)) => Ok(*x),
+            Some(OscType::Int(x)) => Ok(*x as f64),
+            _ => Err(ServerError::BadArgument(arg_idx, "a number")),
+        }
+    }
+}
+
+use clap::Parser;
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about=None)]
+struct Args {
+    #[arg(short, long, default_value = "127.0.0.1:9000")]
+    address: String,
+
+    /// Go through the tiny2d daemon instead of opening the camera directly
+    #[arg(long, value_name = "SOCKET")]
+    remote: Option<Option<PathBuf>>,
+
+    /// Record all camera traffic to a JSONL file
+    #[arg(long, value_name = "FILE")]
+    trace: Option<PathBuf>,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let camera: Arc<dyn OBSBotWebCam + Send + Sync> = match args.remote {
+        Some(socket) => Arc::new(RemoteCamera::wait_for(
+            socket.unwrap_or_else(default_socket_path),
+            "OBSBOT",
+            Duration::from_secs(1),
+        )),
+        None => {
+            let camera = Camera::wait_for("OBSBOT", Duration::from_secs(1));
+            match args.trace.map(Trace::create).transpose() {
+                Ok(Some(trace)) => Arc::new(camera.with_trace(trace)),
+                Ok(None) => Arc::new(camera),
+                Err(err) => {
+                    eprintln!("Error: trace file: {}", err);
+                    return;
+                }
+            }
+        }
+    };
+
+    let server = OBSBotOSCServer {
+        addr: args.address,
+        cameras: vec![camera],
+        motions: RefCell::new(BTreeMap::new()),
+    };
+
+    if let Err(err) = server.run_server() {
+        eprintln!("Error: {}", err);
+    }
+}
@@ -0,0 +1,576 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! Video capture from the camera.
+//!
+//! A [`Capture`] streams frames from a camera's video capture node with V4L2
+//! memory-mapped buffers, asking for MJPEG and falling back to YUYV. Only
+//! one application can stream from a node at a time; if another already
+//! is, opening fails with [`Error::StreamBusy`]. [`Capture::spawn`] streams
+//! on a new thread for a live preview, keeping the latest decoded frame in a
+//! [`PreviewHandle`]. [`Capture::snapshot`] saves a single frame as JPEG or
+//! PNG.
+
+use crate::{usbio, Error};
+use image::RgbaImage;
+use nix::poll::{poll, PollFd, PollFlags, PollTimeout};
+use nix::sys::mman::{mmap, munmap, MapFlags, ProtFlags};
+use nix::{ioctl_readwrite, ioctl_write_ptr};
+use std::ffi::c_void;
+use std::fmt::Display;
+use std::fs::File;
+use std::io;
+use std::num::NonZeroUsize;
+use std::os::fd::{AsFd, AsRawFd};
+use std::path::Path;
+use std::ptr::NonNull;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// The pixel formats a [`Capture`] asks for, in order of preference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// Each frame a JPEG image.
+    Mjpeg,
+    /// Packed 4:2:2 YCbCr, two bytes per pixel.
+    Yuyv,
+}
+
+impl PixelFormat {
+    pub const ALL: [PixelFormat; 2] = [PixelFormat::Mjpeg, PixelFormat::Yuyv];
+
+    fn fourcc(self) -> u32 {
+        let code = match self {
+            PixelFormat::Mjpeg => b"MJPG",
+            PixelFormat::Yuyv => b"YUYV",
+        };
+        u32::from_le_bytes(*code)
+    }
+}
+
+impl Display for PixelFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PixelFormat::Mjpeg => write!(f, "MJPEG"),
+            PixelFormat::Yuyv => write!(f, "YUYV"),
+        }
+    }
+}
+
+/// A frame size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Resolution {
+    /// Sizes the Tiny 2 streams at.
+    pub const COMMON: [Resolution; 4] = [
+        Resolution::new(640, 360),
+        Resolution::new(1280, 720),
+        Resolution::new(1920, 1080),
+        Resolution::new(3840, 2160),
+    ];
+
+    pub const fn new(width: u32, height: u32) -> Self {
+        Resolution { width, height }
+    }
+}
+
+impl Display for Resolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// Width and height separated by `x`, e.g. `1920x1080`.
+impl FromStr for Resolution {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::UnsupportedName("resolution".to_string(), s.to_string());
+        let (width, height) = s.trim().split_once('x').ok_or_else(invalid)?;
+        match (width.parse(), height.parse()) {
+            (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok(Resolution { width, height }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// One frame as the camera sent it.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    pub format: PixelFormat,
+    pub data: Vec<u8>,
+}
+
+impl Frame {
+    /// Decode the frame to 8-bit RGBA.
+    pub fn to_rgba(&self) -> Result<RgbaImage, Error> {
+        match self.format {
+            PixelFormat::Mjpeg => {
+                image::load_from_memory_with_format(&self.data, image::ImageFormat::Jpeg)
+                    .map(|image| image.to_rgba8())
+                    .map_err(|e| Error::Image(e.to_string()))
+            }
+            PixelFormat::Yuyv => {
+                let pixels = (self.width * self.height) as usize;
+                if self.data.len() < pixels * 2 {
+                    return Err(Error::Image(format!(
+                        "YUYV frame of {} bytes is too short for {}x{}",
+                        self.data.len(),
+                        self.width,
+                        self.height
+                    )));
+                }
+                let mut rgba = Vec::with_capacity(pixels * 4);
+                for chunk in self.data[..pixels * 2].chunks_exact(4) {
+                    let (y0, u, y1, v) = (chunk[0], chunk[1], chunk[2], chunk[3]);
+                    rgba.extend_from_slice(&yuv_to_rgba(y0, u, v));
+                    rgba.extend_from_slice(&yuv_to_rgba(y1, u, v));
+                }
+                RgbaImage::from_raw(self.width, self.height, rgba)
+                    .ok_or_else(|| Error::Image("odd YUYV frame width".to_string()))
+            }
+        }
+    }
+}
+
+/// BT.601 limited-range YCbCr to RGB.
+fn yuv_to_rgba(y: u8, u: u8, v: u8) -> [u8; 4] {
+    let c = (y as f32 - 16.0) * 1.164;
+    let d = u as f32 - 128.0;
+    let e = v as f32 - 128.0;
+    let clamp = |x: f32| x.round().clamp(0.0, 255.0) as u8;
+    [
+        clamp(c + 1.596 * e),
+        clamp(c - 0.392 * d - 0.813 * e),
+        clamp(c + 2.017 * d),
+        255,
+    ]
+}
+
+/// How many buffers to queue with the driver.
+const BUFFER_COUNT: u32 = 4;
+
+/// Frames to throw away before a snapshot, while the exposure settles after
+/// the stream starts.
+const SNAPSHOT_SKIP: usize = 5;
+
+/// Damaged frames to put up with before a snapshot gives up.
+const SNAPSHOT_TRIES: usize = 3;
+
+const FRAME_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A buffer the driver fills, mapped into our memory.
+struct Mapping {
+    ptr: NonNull<c_void>,
+    len: usize,
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // SAFETY: ptr and len came from a successful mmap and are unmapped
+        // only here.
+        let _ = unsafe { munmap(self.ptr, self.len) };
+    }
+}
+
+/// A video stream from a camera's capture node.
+pub struct Capture {
+    file: File,
+    buffers: Vec<Mapping>,
+    path: String,
+    width: u32,
+    height: u32,
+    format: PixelFormat,
+}
+
+fn errno(e: nix::errno::Errno) -> Error {
+    match e {
+        nix::errno::Errno::EBUSY => Error::StreamBusy,
+        e => Error::USBIOError(e as i32),
+    }
+}
+
+impl Capture {
+    /// Start streaming from the capture node matching `hint`, a path or part
+    /// of the camera's name or bus info, at or near `width` by `height`.
+    pub fn open(hint: &str, width: u32, height: u32) -> Result<Self, Error> {
+        let (file, device) = usbio::open_capture_node(hint)?;
+        let fd = file.as_raw_fd();
+
+        let mut chosen = None;
+        for format in PixelFormat::ALL {
+            let mut request = v4l2_format::capture(width, height, format.fourcc());
+            // SAFETY: request is a v4l2_format the ioctl reads and writes.
+            unsafe { vidioc_s_fmt(fd, &mut request) }.map_err(errno)?;
+            // SAFETY: a capture type format holds a pix format.
+            let pix = unsafe { request.fmt.pix };
+            if pix.pixelformat == format.fourcc() {
+                chosen = Some((format, pix.width, pix.height));
+                break;
+            }
+        }
+        let (format, width, height) = chosen.ok_or_else(|| {
+            Error::UnsupportedName("video format".to_string(), "neither MJPEG nor YUYV".into())
+        })?;
+
+        let mut request = v4l2_requestbuffers {
+            count: BUFFER_COUNT,
+            type_: V4L2_BUF_TYPE_VIDEO_CAPTURE,
+            memory: V4L2_MEMORY_MMAP,
+            ..Default::default()
+        };
+        // SAFETY: request is a v4l2_requestbuffers the ioctl reads and writes.
+        unsafe { vidioc_reqbufs(fd, &mut request) }.map_err(errno)?;
+
+        let mut capture = Capture {
+            file,
+            buffers: Vec::new(),
+            path: device.path,
+            width,
+            height,
+            format,
+        };
+        for index in 0..request.count {
+            let mut buffer = v4l2_buffer::mmap(index);
+            // SAFETY: buffer is a v4l2_buffer the ioctl reads and writes.
+            unsafe { vidioc_querybuf(fd, &mut buffer) }.map_err(errno)?;
+            let len = NonZeroUsize::new(buffer.length as usize)
+                .ok_or(Error::USBIOError(nix::errno::Errno::EINVAL as i32))?;
+            // SAFETY: the driver told us this offset and length to map; the
+            // mapping is dropped before the file is closed.
+            let ptr = unsafe {
+                mmap(
+                    None,
+                    len,
+                    ProtFlags::PROT_READ | ProtFlags::PROT_WRITE,
+                    MapFlags::MAP_SHARED,
+                    capture.file.as_fd(),
+                    buffer.m.offset as i64,
+                )
+            }
+            .map_err(errno)?;
+            capture.buffers.push(Mapping {
+                ptr,
+                len: len.get(),
+            });
+            // SAFETY: as for VIDIOC_QUERYBUF.
+            unsafe { vidioc_qbuf(fd, &mut buffer) }.map_err(errno)?;
+        }
+
+        let kind = V4L2_BUF_TYPE_VIDEO_CAPTURE as i32;
+        // SAFETY: VIDIOC_STREAMON reads a buffer type.
+        unsafe { vidioc_streamon(fd, &kind) }.map_err(errno)?;
+        Ok(capture)
+    }
+
+    /// The path of the capture node.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The frame size and format the driver settled on.
+    pub fn format(&self) -> (u32, u32, PixelFormat) {
+        (self.width, self.height, self.format)
+    }
+
+    /// Wait up to `timeout` for the next frame. Frames the driver marks as
+    /// damaged are skipped.
+    pub fn next_frame(&mut self, timeout: Duration) -> Result<Frame, Error> {
+        let fd = self.file.as_raw_fd();
+        loop {
+            let mut fds = [PollFd::new(self.file.as_fd(), PollFlags::POLLIN)];
+            let timeout = PollTimeout::try_from(timeout).unwrap_or(PollTimeout::MAX);
+            if poll(&mut fds, timeout).map_err(errno)? == 0 {
+                return Err(
+                    io::Error::new(io::ErrorKind::TimedOut, "no frame from the camera").into(),
+                );
+            }
+
+            let mut buffer = v4l2_buffer::mmap(0);
+            // SAFETY: buffer is a v4l2_buffer the ioctl reads and writes.
+            unsafe { vidioc_dqbuf(fd, &mut buffer) }.map_err(errno)?;
+            let mapping = &self.buffers[buffer.index as usize];
+            let used = (buffer.bytesused as usize).min(mapping.len);
+            let frame = (buffer.flags & V4L2_BUF_FLAG_ERROR == 0 && used > 0).then(|| {
+                // SAFETY: the driver has filled `used` bytes of this mapping
+                // and won't touch it again until it is queued.
+                let data =
+                    unsafe { std::slice::from_raw_parts(mapping.ptr.as_ptr() as *const u8, used) };
+                Frame {
+                    width: self.width,
+                    height: self.height,
+                    format: self.format,
+                    data: data.to_vec(),
+                }
+            });
+            // SAFETY: as for VIDIOC_DQBUF.
+            unsafe { vidioc_qbuf(fd, &mut buffer) }.map_err(errno)?;
+            if let Some(frame) = frame {
+                return Ok(frame);
+            }
+        }
+    }
+
+    /// Save a frame from the capture node matching `hint`, at or near `size`,
+    /// to `path` as JPEG or PNG according to its extension. Returns the size
+    /// the camera sent.
+    pub fn snapshot(hint: &str, size: Resolution, path: &Path) -> Result<Resolution, Error> {
+        let format = match image::ImageFormat::from_path(path) {
+            Ok(format @ (image::ImageFormat::Jpeg | image::ImageFormat::Png)) => format,
+            _ => {
+                return Err(Error::UnsupportedName(
+                    "image file name, expected .jpg or .png".to_string(),
+                    path.display().to_string(),
+                ))
+            }
+        };
+        let mut capture = Capture::open(hint, size.width, size.height)?;
+        for _ in 0..SNAPSHOT_SKIP {
+            capture.next_frame(FRAME_TIMEOUT)?;
+        }
+        let mut tries = 0;
+        let image = loop {
+            match capture.next_frame(FRAME_TIMEOUT)?.to_rgba() {
+                Ok(image) => break image,
+                Err(e) if tries + 1 >= SNAPSHOT_TRIES => return Err(e),
+                Err(_) => tries += 1,
+            }
+        };
+        let size = Resolution::new(image.width(), image.height());
+        // JPEG has no alpha channel, and the camera sends none anyway.
+        image::DynamicImage::ImageRgba8(image)
+            .to_rgb8()
+            .save_with_format(path, format)
+            .map_err(|e| Error::Image(e.to_string()))?;
+        Ok(size)
+    }
+
+    /// Stream on a new thread, decoding each frame, until the handle is
+    /// dropped.
+    pub fn spawn(hint: &str, width: u32, height: u32) -> PreviewHandle {
+        let stop = Arc::new(AtomicBool::new(false));
+        let latest = Arc::new(Mutex::new(None));
+        let (hint, flag, slot) = (hint.to_string(), stop.clone(), latest.clone());
+        let thread = thread::spawn(move || {
+            let mut capture = Capture::open(&hint, width, height)?;
+            while !flag.load(Ordering::Relaxed) {
+                let frame = capture.next_frame(FRAME_TIMEOUT)?;
+                // A damaged JPEG now and then is normal; wait for the next.
+                if let Ok(image) = frame.to_rgba() {
+                    *slot.lock().expect("preview lock poisoned") = Some(image);
+                }
+            }
+            Ok(())
+        });
+        PreviewHandle {
+            stop,
+            latest,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        let kind = V4L2_BUF_TYPE_VIDEO_CAPTURE as i32;
+        // SAFETY: VIDIOC_STREAMOFF reads a buffer type. The mappings are
+        // dropped after this, and the file after them.
+        let _ = unsafe { vidioc_streamoff(self.file.as_raw_fd(), &kind) };
+        self.buffers.clear();
+    }
+}
+
+/// A stream running on its own thread. Dropping the handle stops it.
+#[derive(Debug)]
+#[must_use = "dropping a PreviewHandle stops the stream"]
+pub struct PreviewHandle {
+    stop: Arc<AtomicBool>,
+    latest: Arc<Mutex<Option<RgbaImage>>>,
+    thread: Option<JoinHandle<Result<(), Error>>>,
+}
+
+impl PreviewHandle {
+    /// The newest frame since the last call, if there is one.
+    pub fn take_frame(&self) -> Option<RgbaImage> {
+        self.latest.lock().expect("preview lock poisoned").take()
+    }
+
+    /// Stop streaming after the current frame.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the stream has ended, because it was stopped or failed.
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(|t| t.is_finished())
+    }
+
+    /// Wait for the stream to end, returning the error that ended it.
+    pub fn join(mut self) -> Result<(), Error> {
+        match self.thread.take() {
+            Some(thread) => thread.join().expect("capture thread panicked"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for PreviewHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+const V4L2_BUF_TYPE_VIDEO_CAPTURE: u32 = 1;
+const V4L2_MEMORY_MMAP: u32 = 1;
+const V4L2_FIELD_ANY: u32 = 0;
+const V4L2_BUF_FLAG_ERROR: u32 = 0x0040;
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Default, Debug)]
+struct v4l2_pix_format {
+    width: u32,
+    height: u32,
+    pixelformat: u32,
+    field: u32,
+    bytesperline: u32,
+    sizeimage: u32,
+    colorspace: u32,
+    priv_: u32,
+    flags: u32,
+    ycbcr_enc: u32,
+    quantization: u32,
+    xfer_func: u32,
+}
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone)]
+union v4l2_format_union {
+    pix: v4l2_pix_format,
+    // The kernel's union is 200 bytes and pointer-aligned.
+    raw_data: [u64; 25],
+}
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone)]
+struct v4l2_format {
+    type_: u32,
+    fmt: v4l2_format_union,
+}
+
+impl v4l2_format {
+    fn capture(width: u32, height: u32, pixelformat: u32) -> Self {
+        let mut format = v4l2_format {
+            type_: V4L2_BUF_TYPE_VIDEO_CAPTURE,
+            fmt: v4l2_format_union { raw_data: [0; 25] },
+        };
+        format.fmt.pix = v4l2_pix_format {
+            width,
+            height,
+            pixelformat,
+            field: V4L2_FIELD_ANY,
+            ..Default::default()
+        };
+        format
+    }
+}
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Default, Debug)]
+struct v4l2_requestbuffers {
+    count: u32,
+    type_: u32,
+    memory: u32,
+    capabilities: u32,
+    flags: u8,
+    reserved: [u8; 3],
+}
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Default, Debug)]
+struct v4l2_timecode {
+    type_: u32,
+    flags: u32,
+    frames: u8,
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    userbits: [u8; 4],
+}
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone)]
+union v4l2_buffer_m {
+    offset: u32,
+    userptr: std::ffi::c_ulong,
+}
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone)]
+struct v4l2_buffer {
+    index: u32,
+    type_: u32,
+    bytesused: u32,
+    flags: u32,
+    field: u32,
+    timestamp: nix::libc::timeval,
+    timecode: v4l2_timecode,
+    sequence: u32,
+    memory: u32,
+    m: v4l2_buffer_m,
+    length: u32,
+    reserved2: u32,
+    request_fd: i32,
+}
+
+impl v4l2_buffer {
+    fn mmap(index: u32) -> Self {
+        v4l2_buffer {
+            index,
+            type_: V4L2_BUF_TYPE_VIDEO_CAPTURE,
+            bytesused: 0,
+            flags: 0,
+            field: 0,
+            timestamp: nix::libc::timeval {
+                tv_sec: 0,
+                tv_usec: 0,
+            },
+            timecode: v4l2_timecode::default(),
+            sequence: 0,
+            memory: V4L2_MEMORY_MMAP,
+            m: v4l2_buffer_m { userptr: 0 },
+            length: 0,
+            reserved2: 0,
+            request_fd: 0,
+        }
+    }
+}
+
+// VIDIOC_S_FMT = _IOWR('V', 5, struct v4l2_format)
+// VIDIOC_REQBUFS = _IOWR('V', 8, struct v4l2_requestbuffers)
+// VIDIOC_QUERYBUF = _IOWR('V', 9, struct v4l2_buffer)
+// VIDIOC_QBUF = _IOWR('V', 15, struct v4l2_buffer)
+// VIDIOC_DQBUF = _IOWR('V', 17, struct v4l2_buffer)
+// VIDIOC_STREAMON = _IOW('V', 18, int)
+// VIDIOC_STREAMOFF = _IOW('V', 19, int)
+ioctl_readwrite!(vidioc_s_fmt, b'V', 5, v4l2_format);
+ioctl_readwrite!(vidioc_reqbufs, b'V', 8, v4l2_requestbuffers);
+ioctl_readwrite!(vidioc_querybuf, b'V', 9, v4l2_buffer);
+ioctl_readwrite!(vidioc_qbuf, b'V', 15, v4l2_buffer);
+ioctl_readwrite!(vidioc_dqbuf, b'V', 17, v4l2_buffer);
+ioctl_write_ptr!(vidioc_streamon, b'V', 18, i32);
+ioctl_write_ptr!(vidioc_streamoff, b'V', 19, i32);
@@ -0,0 +1,824 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+use clap::builder::{PossibleValue, PossibleValuesParser};
+use clap::{Parser, Subcommand};
+use std::io::BufRead;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+use tiny2::capture::{Capture, Resolution};
+use tiny2::explore::{self, Explorer, Probe, Step};
+use tiny2::motion::{Easing, Move, PtzPosition};
+use tiny2::pcap::{self, ControlRequest};
+use tiny2::settings::{self, Kind, Setting, SETTINGS};
+use tiny2::{
+    remote::default_socket_path, Angle, Camera, CameraState, Error, FOVMode, Limit, OBSBotWebCam,
+    Preset, PresetStore, Profile, RemoteCamera, SoftLimits, Trace,
+};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Control OBSBOT cameras from the command line", long_about = None)]
+struct Args {
+    /// Camera device path, or part of its name or bus info
+    #[arg(short, long, default_value = "OBSBOT", global = true)]
+    device: String,
+
+    /// Print output as JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Log extension-unit commands to stderr
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// Go through the tiny2d daemon instead of opening the camera directly
+    #[arg(long, global = true, value_name = "SOCKET")]
+    remote: Option<Option<PathBuf>>,
+
+    /// Record all camera traffic to a JSONL file
+    #[arg(long, global = true, value_name = "FILE")]
+    trace: Option<PathBuf>,
+
+    /// Use a simulated camera instead of a real one
+    #[arg(long, global = true, conflicts_with = "remote")]
+    simulate: bool,
+
+    /// Clamp pan, tilt, zoom and focus to the device's range instead of
+    /// failing
+    #[arg(long, global = true, conflicts_with = "remote")]
+    clamp: bool,
+
+    /// Send extension-unit commands whose bytes haven't been confirmed on
+    /// a device: tracking mode, auto zoom, target lock and face focus
+    #[arg(long, global = true, conflicts_with = "remote")]
+    unverified: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List video devices
+    List,
+    /// Show the camera's current settings
+    Status,
+    /// Change a setting, e.g. `set ai-mode upper-body` or `set hdr on`
+    Set {
+        #[arg(value_parser = setting_names())]
+        setting: String,
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+    },
+    /// Show the current value of one setting, or of every readable one
+    Get {
+        #[arg(value_parser = setting_names())]
+        setting: Option<String>,
+    },
+    /// Move the camera to a pan/tilt/zoom position. Pan and tilt are in
+    /// arc-seconds, or degrees with a `deg` suffix, e.g. `--pan -12.5deg`.
+    Ptz {
+        #[arg(long, allow_hyphen_values = true)]
+        pan: Option<Angle>,
+        #[arg(long, allow_hyphen_values = true)]
+        tilt: Option<Angle>,
+        #[arg(long, allow_hyphen_values = true)]
+        zoom: Option<i32>,
+        /// Move by the given amounts instead of to them
+        #[arg(long, conflicts_with_all = ["duration", "speed", "zoom_speed"])]
+        relative: bool,
+        #[command(flatten)]
+        motion: MotionArgs,
+    },
+    /// Save and recall pan/tilt/zoom presets
+    Preset {
+        #[command(subcommand)]
+        action: PresetAction,
+    },
+    /// Show or change the soft limits on pan, tilt and zoom.
+    ///
+    /// The camera won't be moved outside them by any command. They are
+    /// saved per camera.
+    Limits {
+        #[command(subcommand)]
+        action: Option<LimitsAction>,
+    },
+    /// Show the effective field of view, or set the FOV mode and zoom to
+    /// get a given one, in degrees.
+    ///
+    /// The camera can't report its FOV mode, so showing the field of view
+    /// needs `--mode`, unless it goes through the daemon after the mode was
+    /// set there.
+    Fov {
+        #[arg(long, conflicts_with = "vertical")]
+        horizontal: Option<f64>,
+        #[arg(long)]
+        vertical: Option<f64>,
+        /// The FOV mode the camera is in, e.g. `--mode narrow`
+        #[arg(long, conflicts_with_all = ["horizontal", "vertical"])]
+        mode: Option<FOVMode>,
+    },
+    /// Save a frame from the camera as a JPEG or PNG image, e.g.
+    /// `snapshot out.jpg`
+    Snapshot {
+        /// Where to save the image; the extension picks the format
+        path: PathBuf,
+        /// Frame size to ask the camera for; it may pick the nearest it has
+        #[arg(long, default_value = "1920x1080")]
+        size: Resolution,
+    },
+    /// Save and apply camera profiles
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+    /// Send a raw command to an extension-unit selector, e.g. `raw 06 010101`
+    Raw {
+        #[arg(value_parser = parse_selector)]
+        selector: u8,
+        hex: String,
+    },
+    /// Dump the GET_CUR buffer of an extension-unit selector, e.g. `dump 02`
+    Dump {
+        #[arg(value_parser = parse_selector)]
+        selector: u8,
+    },
+    /// Interactively send raw commands and show which status bytes change.
+    ///
+    /// Reads lines of `[selector] hex` from stdin (selector defaults to 06),
+    /// plus `history`, `export FILE` and `quit`.
+    Explore {
+        /// Save the session history as JSON on exit
+        #[arg(long, value_name = "FILE")]
+        export: Option<PathBuf>,
+    },
+    /// Decode the extension-unit requests in a USBPcap capture (pcap or pcapng)
+    Import {
+        file: PathBuf,
+        /// Show every class control request, not just unit 2 selectors 0x02
+        /// and 0x06, including GET_LEN
+        #[arg(long)]
+        all: bool,
+    },
+    /// Run every known setter and map which status bytes each one changes.
+    ///
+    /// This changes the camera's settings; those that can be read back are
+    /// restored afterwards, and the others are listed.
+    Sweep {
+        /// Time to let the camera settle after each setter, in milliseconds
+        #[arg(long, default_value_t = 500)]
+        settle_ms: u64,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PresetAction {
+    /// List saved presets
+    List,
+    /// Save the current position as a preset
+    Save { name: String },
+    /// Move to a saved preset
+    Recall {
+        name: String,
+        #[command(flatten)]
+        motion: MotionArgs,
+    },
+    /// Delete a saved preset
+    Remove { name: String },
+}
+
+/// Options for moving smoothly instead of jumping to a position.
+#[derive(clap::Args, Debug)]
+struct MotionArgs {
+    /// Take this many seconds to get there
+    #[arg(long, value_name = "SECONDS", value_parser = parse_seconds)]
+    duration: Option<Duration>,
+    /// Move pan and tilt at this many arc-seconds per second
+    #[arg(long, conflicts_with = "duration", value_parser = parse_speed)]
+    speed: Option<f64>,
+    /// Move zoom at this many steps per second. Without it, zoom keeps pace
+    /// with pan and tilt.
+    #[arg(long, conflicts_with = "duration", value_parser = parse_speed)]
+    zoom_speed: Option<f64>,
+    /// linear, ease-in, ease-out or ease-in-out
+    #[arg(long, default_value = "ease-in-out")]
+    easing: Easing,
+}
+
+impl MotionArgs {
+    /// The smooth move to `target`, or `None` to jump straight there.
+    fn to(&self, target: PtzPosition) -> Option<Move> {
+        let motion = Move::to(target).easing(self.easing);
+        match (self.duration, self.speed, self.zoom_speed) {
+            (Some(duration), _, _) => Some(motion.over(duration)),
+            (None, None, None) => None,
+            (None, speed, zoom_speed) => {
+                Some(motion.at_speed(speed.unwrap_or_default(), zoom_speed.unwrap_or_default()))
+            }
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum LimitsAction {
+    /// Change the given limits and keep the others.
+    ///
+    /// Pan and tilt are in arc-seconds, or degrees with a `deg` suffix.
+    Set {
+        #[arg(long, allow_hyphen_values = true)]
+        pan_min: Option<Angle>,
+        #[arg(long, allow_hyphen_values = true)]
+        pan_max: Option<Angle>,
+        #[arg(long, allow_hyphen_values = true)]
+        tilt_min: Option<Angle>,
+        #[arg(long, allow_hyphen_values = true)]
+        tilt_max: Option<Angle>,
+        #[arg(long)]
+        zoom_min: Option<i32>,
+        #[arg(long)]
+        zoom_max: Option<i32>,
+    },
+    /// Remove the limits on the given axes, or on all of them
+    Clear {
+        #[arg(value_parser = ["pan", "tilt", "zoom"])]
+        axes: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ProfileAction {
+    /// Apply a TOML or JSON profile to the camera
+    Apply { file: String },
+    /// Save the camera's current settings as a profile
+    Save { file: String },
+}
+
+/// What `set` accepts for a setting, for the help text.
+fn value_help(setting: &Setting) -> String {
+    match setting.kind {
+        Kind::Toggle => "on or off".to_string(),
+        Kind::Choice => {
+            let names: Vec<String> = setting
+                .choices()
+                .iter()
+                .map(|c| c.to_lowercase().replace(' ', "-"))
+                .collect();
+            names.join(", ")
+        }
+        Kind::Range => match setting.units {
+            Some(units) => format!("integer, {}", units),
+            None => "integer".to_string(),
+        },
+    }
+}
+
+/// Setting names from the registry, with their help.
+fn setting_names() -> PossibleValuesParser {
+    PossibleValuesParser::new(SETTINGS.iter().map(|setting| {
+        PossibleValue::new(setting.name).help(format!("{} ({})", setting.help, value_help(setting)))
+    }))
+}
+
+fn parse_selector(s: &str) -> Result<u8, String> {
+    u8::from_str_radix(s.trim_start_matches("0x"), 16).map_err(|e| e.to_string())
+}
+
+fn parse_speed(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed > 0.0 => Ok(speed),
+        Ok(_) => Err("must be a positive number".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_seconds(s: &str) -> Result<Duration, String> {
+    let secs = parse_speed(s)?;
+    Duration::try_from_secs_f64(secs).map_err(|e| e.to_string())
+}
+
+fn json<T: serde::Serialize>(value: &T) -> Result<(), Error> {
+    let s = serde_json::to_string_pretty(value).map_err(|e| Error::ProfileFormat(e.to_string()))?;
+    println!("{}", s);
+    Ok(())
+}
+
+fn print_limits(limits: &SoftLimits) {
+    let end = |v: Option<i32>, angle: bool| match v {
+        Some(v) if angle => Angle::from_arcsec(v).to_string(),
+        Some(v) => v.to_string(),
+        None => "-".to_string(),
+    };
+    for (name, limit, angle) in [
+        ("pan", limits.pan, true),
+        ("tilt", limits.tilt, true),
+        ("zoom", limits.zoom, false),
+    ] {
+        println!(
+            "{:<6}{:>10} .. {}",
+            format!("{}:", name),
+            end(limit.min, angle),
+            end(limit.max, angle)
+        );
+    }
+}
+
+fn print_step(step: &Step) {
+    if let Some(e) = &step.error {
+        println!("command failed: {}", e);
+    }
+    let changes = step.changes();
+    if changes.is_empty() {
+        println!("no changes");
+        return;
+    }
+    for buffer in &step.after {
+        if changes.iter().any(|c| c.selector == buffer.selector) {
+            println!("selector 0x{:02x}:", buffer.selector);
+            print!("{}", explore::hexdump(buffer, &changes));
+        }
+    }
+    for change in &changes {
+        println!("  {}", change);
+    }
+}
+
+/// Run one line of an `explore` session. Returns false to end the session.
+fn explore_line(explorer: &mut Explorer, camera: &dyn OBSBotWebCam, line: &str) -> bool {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let result = match words.as_slice() {
+        [] => Ok(()),
+        ["quit" | "exit"] => return false,
+        ["history"] => {
+            for (i, step) in explorer.history.iter().enumerate() {
+                let changes = step.changes();
+                println!(
+                    "{:3}  0x{:02x} {}  {} change(s){}",
+                    i + 1,
+                    step.selector,
+                    hex::encode(&step.command),
+                    changes.len(),
+                    if step.error.is_some() { ", failed" } else { "" }
+                );
+            }
+            Ok(())
+        }
+        ["export", file] => explorer.save(file),
+        [hex] => send_hex(explorer, camera, "06", hex),
+        [selector, hex] => send_hex(explorer, camera, selector, hex),
+        _ => {
+            eprintln!("expected `[selector] hex`, `history`, `export FILE` or `quit`");
+            Ok(())
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+    }
+    true
+}
+
+fn send_hex(
+    explorer: &mut Explorer,
+    camera: &dyn OBSBotWebCam,
+    selector: &str,
+    hex: &str,
+) -> Result<(), Error> {
+    let selector =
+        parse_selector(selector).map_err(|e| Error::UnsupportedName("selector".to_string(), e))?;
+    let cmd = hex::decode(hex.trim_start_matches("0x"))
+        .map_err(|e| Error::UnsupportedName("hex string".to_string(), e.to_string()))?;
+    print_step(explorer.send(camera, selector, &cmd)?);
+    Ok(())
+}
+
+fn print_requests(requests: &[ControlRequest]) {
+    let start = requests.first().map(|r| r.time).unwrap_or_default();
+    let mut unknown = 0;
+    for r in requests {
+        let description = r.describe().unwrap_or_else(|| {
+            unknown += 1;
+            "UNKNOWN".to_string()
+        });
+        let status = match r.status {
+            Some(0) => String::new(),
+            Some(status) => format!(" (status 0x{:08x})", status),
+            None => " (no completion)".to_string(),
+        };
+        println!(
+            "{:9.3}  {:<8} unit {} sel 0x{:02x}  {:<24} {}{}",
+            r.time - start,
+            r.request_name(),
+            r.unit,
+            r.selector,
+            hex::encode(r.trimmed_data()),
+            description,
+            status
+        );
+    }
+    eprintln!("{} request(s), {} unknown", requests.len(), unknown);
+}
+
+fn print_byte_map(probes: &[Probe]) {
+    for probe in probes.iter().filter(|p| p.error.is_some()) {
+        eprintln!(
+            "{} failed: {}",
+            probe.setting,
+            probe.error.as_ref().unwrap()
+        );
+    }
+    for ((selector, offset), settings) in explore::byte_map(probes) {
+        let field = match settings::status_command_at(offset) {
+            Some(command) if selector == 0x06 => command.name,
+            _ => "?",
+        };
+        println!(
+            "0x{:02x}[0x{:02x}]  {:<14} {}",
+            selector,
+            offset,
+            field,
+            settings.join(", ")
+        );
+    }
+}
+
+fn print_state(state: &CameraState) {
+    let status = &state.status;
+    let opt = |v: Option<String>| v.unwrap_or_else(|| "n/a".to_string());
+    println!(
+        "AI mode:       {}",
+        status
+            .ai_mode
+            .map_or_else(|| "unknown".to_string(), |m| m.to_string())
+    );
+    println!("HDR:           {}", status.hdr_on);
+    println!(
+        "Anti-flicker:  {}",
+        opt(state.power_line_frequency.map(|v| v.to_string()))
+    );
+    println!(
+        "Focus mode:    {}",
+        opt(state.focus_mode.map(|v| v.to_string()))
+    );
+    println!("Focus:         {}", opt(state.focus.map(|v| v.to_string())));
+    println!(
+        "Mirror:        {}",
+        opt(state.mirror.map(|v| v.to_string()))
+    );
+    println!("Flip:          {}", opt(state.flip.map(|v| v.to_string())));
+    println!("Pan:           {}", opt(state.pan.map(|v| v.to_string())));
+    println!("Tilt:          {}", opt(state.tilt.map(|v| v.to_string())));
+    println!("Zoom:          {}", opt(state.zoom.map(|v| v.to_string())));
+    // Read from offsets that were guessed, so label them as such.
+    let unverified = &status.unverified;
+    println!("Unverified:");
+    println!(
+        "  Tracking mode: {}",
+        unverified
+            .tracking_mode
+            .map_or_else(|| "unknown".to_string(), |m| m.to_string())
+    );
+    println!("  Auto zoom:     {}", unverified.auto_zoom);
+    println!("  Target lock:   {}", unverified.target_lock);
+}
+
+/// Print `setting`'s current value, or "n/a" if it can't be read.
+fn print_setting(camera: &dyn OBSBotWebCam, setting: &Setting, json: bool) -> Result<(), Error> {
+    let value = setting.get(camera)?.map(|v| setting.format(v));
+    if json {
+        return self::json(&serde_json::json!({ setting.name: value }));
+    }
+    println!(
+        "{:<14} {}",
+        format!("{}:", setting.name),
+        value.as_deref().unwrap_or("n/a")
+    );
+    Ok(())
+}
+
+fn run(args: Args) -> Result<(), Error> {
+    let socket = args
+        .remote
+        .clone()
+        .map(|socket| socket.unwrap_or_else(default_socket_path));
+    let open = || -> Result<Box<dyn OBSBotWebCam>, Error> {
+        if let Some(socket) = &socket {
+            return Ok(Box::new(RemoteCamera::connect(socket, &args.device)?));
+        }
+        let mut camera = if args.simulate {
+            Camera::simulated()
+        } else {
+            Camera::new(&args.device)?
+        };
+        camera.set_verbose(args.verbose);
+        camera.set_clamp(args.clamp);
+        camera.set_unverified(args.unverified);
+        if let Some(path) = &args.trace {
+            camera = camera.with_trace(Trace::create(path)?);
+        }
+        Ok(Box::new(camera))
+    };
+
+    match args.command {
+        Command::List => {
+            let devices = match &socket {
+                Some(socket) => RemoteCamera::connect(socket, &args.device)?.call("list", ())?,
+                None => Camera::list(),
+            };
+            if args.json {
+                json(&devices)?;
+            } else {
+                for d in devices {
+                    println!("{}\t{}\t{}", d.path, d.card, d.bus_info);
+                }
+            }
+        }
+        Command::Status => {
+            let state = open()?.get_state()?;
+            if args.json {
+                json(&state)?;
+            } else {
+                print_state(&state);
+            }
+        }
+        Command::Set { setting, value } => {
+            let setting = settings::lookup(&setting)?;
+            setting.set(&*open()?, setting.parse(&value)?)?;
+        }
+        Command::Get { setting } => {
+            let camera = open()?;
+            match setting {
+                Some(name) => print_setting(&*camera, settings::lookup(&name)?, args.json)?,
+                None if args.json => {
+                    let mut values = serde_json::Map::new();
+                    for setting in SETTINGS.iter().filter(|s| s.is_readable()) {
+                        let value = setting.get(&*camera).ok().flatten();
+                        values.insert(
+                            setting.name.to_string(),
+                            value.map(|v| setting.format(v)).into(),
+                        );
+                    }
+                    json(&values)?;
+                }
+                None => {
+                    for setting in SETTINGS.iter().filter(|s| s.is_readable()) {
+                        if let Err(e) = print_setting(&*camera, setting, false) {
+                            println!("{:<14} n/a ({})", format!("{}:", setting.name), e);
+                        }
+                    }
+                }
+            }
+        }
+        Command::Ptz {
+            pan,
+            tilt,
+            zoom,
+            relative,
+            motion,
+        } => {
+            let camera = open()?;
+            let (pan, tilt) = (pan.map(Angle::arcsec), tilt.map(Angle::arcsec));
+            if let Some(motion) = motion.to(PtzPosition { pan, tilt, zoom }) {
+                motion.run(&*camera, &AtomicBool::new(false))?;
+            } else if relative {
+                if let Some(v) = pan {
+                    camera.pan_relative(v)?;
+                }
+                if let Some(v) = tilt {
+                    camera.tilt_relative(v)?;
+                }
+                if let Some(v) = zoom {
+                    camera.zoom_relative(v)?;
+                }
+            } else {
+                if let Some(v) = pan {
+                    camera.set_pan(v)?;
+                }
+                if let Some(v) = tilt {
+                    camera.set_tilt(v)?;
+                }
+                if let Some(v) = zoom {
+                    camera.set_zoom(v)?;
+                }
+            }
+        }
+        Command::Preset { action } => {
+            let path = PresetStore::default_path();
+            let mut store = PresetStore::load(&path)?;
+            match action {
+                PresetAction::List => {
+                    if args.json {
+                        json(&store.presets)?;
+                    } else {
+                        for (name, p) in &store.presets {
+                            println!("{}\tpan {}\ttilt {}\tzoom {}", name, p.pan, p.tilt, p.zoom);
+                        }
+                    }
+                }
+                PresetAction::Save { name } => {
+                    store.presets.insert(name, Preset::capture(&*open()?)?);
+                    store.save(&path)?;
+                }
+                PresetAction::Recall { name, motion } => {
+                    let preset = *store.get(&name)?;
+                    match motion.to(preset.into()) {
+                        Some(motion) => {
+                            motion.run(&*open()?, &AtomicBool::new(false))?;
+                        }
+                        None => preset.recall(&*open()?)?,
+                    }
+                }
+                PresetAction::Remove { name } => {
+                    if store.presets.remove(&name).is_none() {
+                        return Err(Error::NoSuchPreset(name));
+                    }
+                    store.save(&path)?;
+                }
+            }
+        }
+        Command::Limits { action } => {
+            let camera = open()?;
+            let mut limits = camera.get_soft_limits()?;
+            match action {
+                None => {}
+                Some(LimitsAction::Set {
+                    pan_min,
+                    pan_max,
+                    tilt_min,
+                    tilt_max,
+                    zoom_min,
+                    zoom_max,
+                }) => {
+                    let arcsec = |a: Option<Angle>| a.map(Angle::arcsec);
+                    limits.pan.min = arcsec(pan_min).or(limits.pan.min);
+                    limits.pan.max = arcsec(pan_max).or(limits.pan.max);
+                    limits.tilt.min = arcsec(tilt_min).or(limits.tilt.min);
+                    limits.tilt.max = arcsec(tilt_max).or(limits.tilt.max);
+                    limits.zoom.min = zoom_min.or(limits.zoom.min);
+                    limits.zoom.max = zoom_max.or(limits.zoom.max);
+                    camera.set_soft_limits(limits)?;
+                }
+                Some(LimitsAction::Clear { axes }) => {
+                    let clear = |axis: &str| axes.is_empty() || axes.iter().any(|a| a == axis);
+                    if clear("pan") {
+                        limits.pan = Limit::default();
+                    }
+                    if clear("tilt") {
+                        limits.tilt = Limit::default();
+                    }
+                    if clear("zoom") {
+                        limits.zoom = Limit::default();
+                    }
+                    camera.set_soft_limits(limits)?;
+                }
+            }
+            if args.json {
+                json(&limits)?;
+            } else {
+                print_limits(&limits);
+            }
+        }
+        Command::Fov {
+            horizontal,
+            vertical,
+            mode,
+        } => {
+            let camera = open()?;
+            let fov = match (horizontal, vertical, mode) {
+                (Some(degrees), _, _) => camera.set_horizontal_fov(degrees)?,
+                (None, Some(degrees), _) => camera.set_vertical_fov(degrees)?,
+                (None, None, Some(mode)) => {
+                    camera.fov_calibration()?.at(mode, camera.get_zoom_ratio()?)
+                }
+                (None, None, None) => camera.get_field_of_view()?,
+            };
+            let mode = mode
+                .or(camera.get_fov_mode()?)
+                .ok_or(Error::FovModeUnknown)?;
+            let zoom = camera.get_zoom_ratio()?;
+            if args.json {
+                json(&serde_json::json!({
+                    "horizontal": fov.horizontal,
+                    "vertical": fov.vertical,
+                    "mode": mode,
+                    "zoom": zoom,
+                }))?;
+            } else {
+                println!("horizontal: {:.1}°", fov.horizontal);
+                println!("vertical:   {:.1}°", fov.vertical);
+                println!("mode:       {}", mode);
+                println!("zoom:       {}", zoom);
+            }
+        }
+        Command::Snapshot { path, size } => {
+            let size = Capture::snapshot(&args.device, size, &path)?;
+            if args.json {
+                json(&serde_json::json!({
+                    "path": path,
+                    "width": size.width,
+                    "height": size.height,
+                }))?;
+            } else {
+                println!("saved {} frame to {}", size, path.display());
+            }
+        }
+        Command::Profile { action } => match action {
+            ProfileAction::Apply { file } => {
+                let report = Profile::load(&file)?.apply(&*open()?);
+                if args.json {
+                    let failed: Vec<(&str, String)> = report
+                        .failed
+                        .iter()
+                        .map(|(field, e)| (*field, e.to_string()))
+                        .collect();
+                    json(&serde_json::json!({
+                        "applied": report.applied,
+                        "failed": failed,
+                    }))?;
+                } else {
+                    for (field, e) in &report.failed {
+                        eprintln!("Failed to apply {}: {}", field, e);
+                    }
+                }
+                if !report.is_ok() {
+                    return Err(Error::ProfileApply(report.failed.len()));
+                }
+            }
+            ProfileAction::Save { file } => Profile::capture(&*open()?)?.save(&file)?,
+        },
+        Command::Raw { selector, hex } => {
+            let cmd = hex::decode(hex.trim_start_matches("0x"))
+                .map_err(|e| Error::UnsupportedName("hex string".to_string(), e.to_string()))?;
+            open()?.send_cmd(0x2, selector, &cmd)?;
+        }
+        Command::Dump { selector } => {
+            let data = open()?.read_selector(selector)?;
+            if args.json {
+                json(&serde_json::json!({
+                    "selector": selector,
+                    "data": hex::encode(data),
+                }))?;
+            } else {
+                hexdump::hexdump(&data);
+            }
+        }
+        Command::Import { file, all } => {
+            let requests: Vec<ControlRequest> = pcap::control_requests(&pcap::read_packets(file)?)?
+                .into_iter()
+                .filter(|r| {
+                    all || (r.unit == 2 && [0x02, 0x06].contains(&r.selector) && r.request != 0x85)
+                })
+                .collect();
+            if args.json {
+                json(&requests)?;
+            } else {
+                print_requests(&requests);
+            }
+        }
+        Command::Sweep { settle_ms } => {
+            let sweep = explore::sweep(&*open()?, Duration::from_millis(settle_ms))?;
+            if args.json {
+                json(&sweep.probes)?;
+            } else {
+                print_byte_map(&sweep.probes);
+            }
+            for (setting, e) in &sweep.unrestored {
+                eprintln!("Failed to restore {}: {}", setting, e);
+            }
+            if !sweep.unrecoverable.is_empty() {
+                eprintln!(
+                    "Warning: left as the sweep set them, since they can't be read back: {}",
+                    sweep.unrecoverable.join(", ")
+                );
+            }
+            if let Some(e) = sweep.error {
+                return Err(e);
+            }
+            if !sweep.unrestored.is_empty() {
+                return Err(Error::SweepRestore(sweep.unrestored.len()));
+            }
+        }
+        Command::Explore { export } => {
+            let camera = open()?;
+            let mut explorer = Explorer::new();
+            for line in std::io::stdin().lock().lines() {
+                if !explore_line(&mut explorer, &*camera, &line?) {
+                    break;
+                }
+            }
+            if let Some(file) = export {
+                explorer.save(file)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,401 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tiny2::remote::{self, Request, CAMERA_ERROR, INTERNAL_ERROR, METHOD_NOT_FOUND, PARSE_ERROR};
+use tiny2::{profile::config_dir, Camera, DeviceInfo, Error, Profile, Trace};
+
+/// A camera match and the profile to apply to it. Every criterion that is
+/// set has to match.
+#[derive(Debug, Clone, Deserialize)]
+struct CameraRule {
+    /// USB serial number.
+    serial: Option<String>,
+    /// Part of the device name, e.g. "OBSBOT Tiny 2".
+    model: Option<String>,
+    profile: PathBuf,
+}
+
+impl CameraRule {
+    fn matches(&self, device: &DeviceInfo) -> bool {
+        self.serial
+            .as_ref()
+            .is_none_or(|s| device.serial.as_ref() == Some(s))
+            && self
+                .model
+                .as_ref()
+                .is_none_or(|m| device.card.contains(m.as_str()))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct Config {
+    /// Only devices whose name contains this are managed.
+    device_filter: String,
+    poll_interval_secs: u64,
+    /// How long to wait after a camera appears before configuring it.
+    settle_ms: u64,
+    #[serde(rename = "camera")]
+    cameras: Vec<CameraRule>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            device_filter: "OBSBOT".to_string(),
+            poll_interval_secs: 2,
+            settle_ms: 1000,
+            cameras: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    fn load(path: &Path) -> Result<Self, Error> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => {
+                toml::from_str(&contents).map_err(|e| Error::ProfileFormat(e.to_string()))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The profile for a device: the first matching rule, or the default
+    /// profile if no rules are configured.
+    fn profile_for(&self, device: &DeviceInfo) -> Option<PathBuf> {
+        if self.cameras.is_empty() {
+            let path = Profile::default_path();
+            return path.exists().then_some(path);
+        }
+        self.cameras
+            .iter()
+            .find(|rule| rule.matches(device))
+            .map(|rule| rule.profile.clone())
+    }
+}
+
+/// What the daemon did for one camera, reported by the `status` method.
+#[derive(Debug, Clone, Serialize)]
+struct CameraEntry {
+    device: DeviceInfo,
+    profile: Option<PathBuf>,
+    applied: Vec<&'static str>,
+    failed: Vec<(&'static str, String)>,
+    error: Option<String>,
+    /// Unix time the profile was applied.
+    applied_at: Option<u64>,
+}
+
+/// A camera the daemon has open.
+struct Managed {
+    camera: Camera,
+    entry: CameraEntry,
+    /// The device node it was opened from, which is a new node after a
+    /// replug even if that happens between polls.
+    node: Option<NodeId>,
+}
+
+/// Inode and device number of a device node.
+type NodeId = (u64, u64);
+
+fn node_id(path: &str) -> Option<NodeId> {
+    std::fs::metadata(path).ok().map(|m| (m.ino(), m.rdev()))
+}
+
+impl Managed {
+    fn matches(&self, hint: &str) -> bool {
+        let device = &self.entry.device;
+        device.path == hint
+            || device.path == format!("/dev/{}", hint)
+            || device.card.contains(hint)
+            || device.bus_info.contains(hint)
+            || device.serial.as_deref() == Some(hint)
+    }
+}
+
+#[derive(Default)]
+struct Daemon {
+    started_at: u64,
+    /// Cameras currently present, by bus info.
+    cameras: BTreeMap<String, Managed>,
+}
+
+/// Lock the daemon even if a request panicked while holding it: the camera
+/// map is still consistent, and one bad request mustn't lock out everyone.
+fn lock(daemon: &Mutex<Daemon>) -> MutexGuard<'_, Daemon> {
+    daemon.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl Daemon {
+    fn find(&self, hint: Option<&str>) -> Result<&Managed, Error> {
+        self.cameras
+            .values()
+            .find(|m| hint.is_none_or(|h| m.matches(h)))
+            .ok_or(Error::NoCameraFound)
+    }
+
+    fn status(&self) -> Value {
+        let cameras: Vec<&CameraEntry> = self.cameras.values().map(|m| &m.entry).collect();
+        json!({"started_at": self.started_at, "cameras": cameras})
+    }
+
+    /// Handle one request. All camera access goes through here with the
+    /// daemon locked, so clients can't interleave commands.
+    fn handle(&self, request: Request) -> Result<Value, (i32, String)> {
+        let camera_error = |e: Error| (CAMERA_ERROR, e.to_string());
+        let params = request.params;
+        match request.method.as_str() {
+            "status" => Ok(self.status()),
+            "list" => {
+                let devices: Vec<&DeviceInfo> =
+                    self.cameras.values().map(|m| &m.entry.device).collect();
+                Ok(json!(devices))
+            }
+            "apply_profile" => {
+                let managed = self.find(params.camera.as_deref()).map_err(camera_error)?;
+                let path: String = serde_json::from_value(params.value)
+                    .map_err(|e| (CAMERA_ERROR, format!("invalid params: {}", e)))?;
+                let report = Profile::load(&path)
+                    .map_err(camera_error)?
+                    .apply(&managed.camera);
+                let failed: Vec<(&str, String)> = report
+                    .failed
+                    .iter()
+                    .map(|(field, e)| (*field, e.to_string()))
+                    .collect();
+                Ok(json!({"applied": report.applied, "failed": failed}))
+            }
+            method => {
+                let managed = self.find(params.camera.as_deref()).map_err(camera_error)?;
+                match remote::dispatch(&managed.camera, method, params.value) {
+                    Some(result) => result.map_err(camera_error),
+                    None => Err((METHOD_NOT_FOUND, format!("unknown method \"{}\"", method))),
+                }
+            }
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn configure(config: &Config, camera: &Camera, device: DeviceInfo) -> CameraEntry {
+    let mut entry = CameraEntry {
+        profile: config.profile_for(&device),
+        device,
+        applied: Vec::new(),
+        failed: Vec::new(),
+        error: None,
+        applied_at: None,
+    };
+    let Some(path) = &entry.profile else {
+        eprintln!("{}: no profile configured", entry.device.path);
+        return entry;
+    };
+
+    match Profile::load(path).map(|profile| profile.apply(camera)) {
+        Ok(report) => {
+            eprintln!(
+                "{}: applied {} ({})",
+                entry.device.path,
+                path.display(),
+                report.applied.join(", ")
+            );
+            for (field, e) in &report.failed {
+                eprintln!("{}: failed to apply {}: {}", entry.device.path, field, e);
+            }
+            entry.applied = report.applied;
+            entry.failed = report
+                .failed
+                .into_iter()
+                .map(|(field, e)| (field, e.to_string()))
+                .collect();
+            entry.applied_at = Some(now());
+        }
+        Err(e) => {
+            eprintln!(
+                "{}: failed to apply {}: {}",
+                entry.device.path,
+                path.display(),
+                e
+            );
+            entry.error = Some(e.to_string());
+        }
+    }
+    entry
+}
+
+/// Poll for cameras, opening and configuring each one when it appears or
+/// is replugged.
+fn watch(config: &Config, daemon: &Mutex<Daemon>, trace: Option<&Trace>) {
+    loop {
+        // A camera can have several video nodes; open it once per bus.
+        let mut devices: BTreeMap<String, DeviceInfo> = BTreeMap::new();
+        for device in Camera::list() {
+            if device.card.contains(&config.device_filter) {
+                devices.entry(device.bus_info.clone()).or_insert(device);
+            }
+        }
+
+        lock(daemon).cameras.retain(|bus, managed| {
+            let device = &managed.entry.device;
+            match devices.get(bus) {
+                None => eprintln!("{}: {} removed", device.path, device.card),
+                Some(now) if node_id(&now.path) != managed.node => {
+                    eprintln!("{}: {} replugged", device.path, device.card)
+                }
+                Some(_) => return true,
+            }
+            false
+        });
+
+        for (bus, device) in &devices {
+            if lock(daemon).cameras.contains_key(bus) {
+                continue;
+            }
+            eprintln!("{}: {} appeared", device.path, device.card);
+            thread::sleep(Duration::from_millis(config.settle_ms));
+            let node = node_id(&device.path);
+            let camera = match Camera::new(&device.path) {
+                Ok(camera) => match trace {
+                    Some(trace) => camera.with_trace(trace.clone()),
+                    None => camera,
+                },
+                Err(e) => {
+                    eprintln!("{}: failed to open: {}", device.path, e);
+                    continue;
+                }
+            };
+            let entry = configure(config, &camera, device.clone());
+            lock(daemon).cameras.insert(
+                bus.clone(),
+                Managed {
+                    camera,
+                    entry,
+                    node,
+                },
+            );
+        }
+
+        thread::sleep(Duration::from_secs(config.poll_interval_secs));
+    }
+}
+
+/// Answer newline-delimited JSON-RPC requests from one client.
+fn serve_client(stream: UnixStream, daemon: Arc<Mutex<Daemon>>) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let id = request.id.clone();
+                let method = request.method.clone();
+                let result =
+                    panic::catch_unwind(AssertUnwindSafe(|| lock(&daemon).handle(request)))
+                        .unwrap_or_else(|_| {
+                            Err((INTERNAL_ERROR, format!("{} failed unexpectedly", method)))
+                        });
+                remote::response(id, result)
+            }
+            Err(e) => remote::response(Value::Null, Err((PARSE_ERROR, e.to_string()))),
+        };
+        writeln!(writer, "{}", reply)?;
+    }
+    Ok(())
+}
+
+fn serve(listener: UnixListener, daemon: Arc<Mutex<Daemon>>) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let daemon = daemon.clone();
+        thread::spawn(move || {
+            if let Err(e) = serve_client(stream, daemon) {
+                eprintln!("client: {}", e);
+            }
+        });
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Own OBSBOT cameras, re-apply profiles on hotplug and share them over a socket", long_about = None)]
+struct Args {
+    /// Config file [default: ~/.config/tiny2/tiny2d.toml]
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+
+    /// JSON-RPC socket [default: $XDG_RUNTIME_DIR/tiny2d.sock]
+    #[arg(short, long)]
+    socket: Option<PathBuf>,
+
+    /// Record all camera traffic to a JSONL file
+    #[arg(long, value_name = "FILE")]
+    trace: Option<PathBuf>,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let config_path = args
+        .config
+        .unwrap_or_else(|| config_dir().join("tiny2d.toml"));
+    let config = match Config::load(&config_path) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error: {}: {}", config_path.display(), e);
+            std::process::exit(1);
+        }
+    };
+
+    let trace = match args.trace.map(Trace::create).transpose() {
+        Ok(trace) => trace,
+        Err(e) => {
+            eprintln!("Error: trace file: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let daemon = Arc::new(Mutex::new(Daemon {
+        started_at: now(),
+        ..Default::default()
+    }));
+
+    let socket_path = args.socket.unwrap_or_else(remote::default_socket_path);
+    // A socket file left by a daemon that died is replaced; one that
+    // answers belongs to a daemon that owns the cameras already.
+    if UnixStream::connect(&socket_path).is_ok() {
+        eprintln!(
+            "Error: another tiny2d is listening on {}",
+            socket_path.display()
+        );
+        std::process::exit(1);
+    }
+    let _ = std::fs::remove_file(&socket_path);
+    match UnixListener::bind(&socket_path) {
+        Ok(listener) => {
+            let daemon = daemon.clone();
+            thread::spawn(move || serve(listener, daemon));
+        }
+        Err(e) => eprintln!("Socket {}: {}", socket_path.display(), e),
+    }
+
+    watch(&config, &daemon, trace.as_ref());
+}
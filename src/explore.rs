@@ -0,0 +1,329 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! Protocol exploration: send raw extension-unit commands and see which
+//! bytes of the readable buffers they change.
+//!
+//! Each command sent through an [`Explorer`] is bracketed by GET_CUR reads of
+//! selectors 0x06 and 0x02, and kept in a history that can be saved as JSON.
+//! [`sweep`] does the same for every known setter, to map which status bytes
+//! each setting moves.
+
+use crate::settings::{self, Kind, Setting, Value, SETTINGS};
+use crate::{Error, OBSBotWebCam};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The selectors snapshotted around every command.
+pub const SELECTORS: [u8; 2] = [0x06, 0x02];
+
+/// The contents of one selector's GET_CUR buffer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Buffer {
+    pub selector: u8,
+    #[serde(with = "crate::hex_bytes")]
+    pub data: Vec<u8>,
+}
+
+/// A byte that differs between two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    pub selector: u8,
+    pub offset: usize,
+    pub before: u8,
+    pub after: u8,
+}
+
+impl Change {
+    /// The command whose value reads back in this byte, if it is known.
+    /// Offsets that haven't been confirmed on a device are marked
+    /// unverified.
+    pub fn field(&self) -> Option<String> {
+        if self.selector != 0x06 {
+            return None;
+        }
+        let command = settings::status_command_at(self.offset)?;
+        Some(if command.is_verified() {
+            command.name.to_string()
+        } else {
+            format!("{} (unverified)", command.name)
+        })
+    }
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "0x{:02x}[0x{:02x}]: {:02x} -> {:02x}",
+            self.selector, self.offset, self.before, self.after
+        )?;
+        if let Some(field) = self.field() {
+            write!(f, "  ({})", field)?;
+        }
+        Ok(())
+    }
+}
+
+/// One command and its effect.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    /// Unix time the command was sent.
+    pub time: u64,
+    pub selector: u8,
+    #[serde(with = "crate::hex_bytes")]
+    pub command: Vec<u8>,
+    /// Why the command failed, if it did.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub before: Vec<Buffer>,
+    pub after: Vec<Buffer>,
+}
+
+impl Step {
+    /// The bytes the command changed, in selector then offset order.
+    pub fn changes(&self) -> Vec<Change> {
+        diff(&self.before, &self.after)
+    }
+}
+
+/// Compare two snapshots selector by selector.
+pub fn diff(before: &[Buffer], after: &[Buffer]) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for b in before {
+        let Some(a) = after.iter().find(|a| a.selector == b.selector) else {
+            continue;
+        };
+        for (offset, (&x, &y)) in b.data.iter().zip(&a.data).enumerate() {
+            if x != y {
+                changes.push(Change {
+                    selector: b.selector,
+                    offset,
+                    before: x,
+                    after: y,
+                });
+            }
+        }
+    }
+    changes
+}
+
+/// A hex dump of `buffer`, 16 bytes per line, with the bytes listed in
+/// `changes` wrapped in brackets.
+pub fn hexdump(buffer: &Buffer, changes: &[Change]) -> String {
+    let mut out = String::new();
+    for (row, chunk) in buffer.data.chunks(16).enumerate() {
+        let _ = write!(out, "{:04x}:", row * 16);
+        for (i, byte) in chunk.iter().enumerate() {
+            let offset = row * 16 + i;
+            let changed = changes
+                .iter()
+                .any(|c| c.selector == buffer.selector && c.offset == offset);
+            if changed {
+                let _ = write!(out, "[{:02x}]", byte);
+            } else {
+                let _ = write!(out, " {:02x} ", byte);
+            }
+        }
+        out.truncate(out.trim_end().len());
+        out.push('\n');
+    }
+    out
+}
+
+/// Read the buffers of all [`SELECTORS`].
+pub fn snapshot(camera: &(impl OBSBotWebCam + ?Sized)) -> Result<Vec<Buffer>, Error> {
+    SELECTORS
+        .iter()
+        .map(|&selector| {
+            Ok(Buffer {
+                selector,
+                data: camera.read_selector(selector)?.to_vec(),
+            })
+        })
+        .collect()
+}
+
+/// A protocol exploration session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Explorer {
+    /// How long to wait after a command before reading the buffers back.
+    #[serde(skip, default = "default_settle")]
+    pub settle: Duration,
+    pub history: Vec<Step>,
+}
+
+fn default_settle() -> Duration {
+    Duration::from_millis(200)
+}
+
+impl Default for Explorer {
+    fn default() -> Self {
+        Explorer {
+            settle: default_settle(),
+            history: Vec::new(),
+        }
+    }
+}
+
+impl Explorer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send `command` to `selector` of unit 2, snapshotting the buffers before
+    /// and after. A command the camera rejects is still recorded, with its
+    /// error; only failing to read the buffers is an error here.
+    pub fn send(
+        &mut self,
+        camera: &(impl OBSBotWebCam + ?Sized),
+        selector: u8,
+        command: &[u8],
+    ) -> Result<&Step, Error> {
+        let before = snapshot(camera)?;
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let error = camera
+            .send_cmd(0x2, selector, command)
+            .err()
+            .map(|e| e.to_string());
+        thread::sleep(self.settle);
+        let after = snapshot(camera)?;
+
+        self.history.push(Step {
+            time,
+            selector,
+            command: command.to_vec(),
+            error,
+            before,
+            after,
+        });
+        Ok(self.history.last().unwrap())
+    }
+
+    /// Write the session history as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let json =
+            serde_json::to_string_pretty(self).map_err(|e| Error::TraceFormat(e.to_string()))?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+/// The effect of one setter call in a [`sweep`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Probe {
+    /// The setting and value set, e.g. `hdr on`.
+    pub setting: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub changes: Vec<Change>,
+}
+
+/// The values [`sweep`] sets `setting` to, in order. Each value is visited
+/// from a different previous one, so both directions of a toggle are seen.
+/// Ranges go to both ends and back to their default, if the device reports
+/// one.
+fn sweep_values(setting: &Setting, camera: &dyn OBSBotWebCam) -> Vec<Value> {
+    match setting.kind {
+        Kind::Toggle => vec![Value::Bool(true), Value::Bool(false)],
+        Kind::Choice => (0..setting.choices().len()).map(Value::Choice).collect(),
+        Kind::Range => match setting.range(camera) {
+            Ok(Some(range)) => [range.maximum, range.minimum, range.default_value]
+                .map(Value::Int)
+                .to_vec(),
+            _ => Vec::new(),
+        },
+    }
+}
+
+/// The result of a [`sweep`].
+#[derive(Debug, Default)]
+pub struct Sweep {
+    pub probes: Vec<Probe>,
+    /// Why probing stopped early, if it did.
+    pub error: Option<Error>,
+    /// Settings that couldn't be set back to their original value.
+    pub unrestored: Vec<(&'static str, Error)>,
+    /// Settings that were changed but can't be read back, so are left as
+    /// the sweep last set them.
+    pub unrecoverable: Vec<&'static str>,
+}
+
+impl Sweep {
+    /// Whether probing finished and every setting that could be restored
+    /// was; see [`Sweep::unrecoverable`] for the ones that couldn't be.
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none() && self.unrestored.is_empty()
+    }
+}
+
+/// Set every setting in [`SETTINGS`] to each of its values in turn and record
+/// which bytes of the selector 0x06 and 0x02 buffers each call changes,
+/// waiting `settle` after each one.
+///
+/// This really does change the camera's settings. Those that can be read
+/// back are restored afterwards, even if probing stopped early, and every
+/// one is tried even if some fail; the others are listed as unrecoverable.
+/// Only failing to read the buffers before anything was changed is an
+/// error here.
+pub fn sweep(camera: &dyn OBSBotWebCam, settle: Duration) -> Result<Sweep, Error> {
+    let mut before = snapshot(camera)?;
+    let mut saved = Vec::new();
+    for setting in &SETTINGS {
+        if let Ok(Some(value)) = setting.get(camera) {
+            saved.push((setting, value));
+        }
+    }
+
+    let mut sweep = Sweep::default();
+    'probe: for setting in &SETTINGS {
+        for value in sweep_values(setting, camera) {
+            let error = setting.set(camera, value).err().map(|e| e.to_string());
+            let unsaved = !saved.iter().any(|(s, _)| s.name == setting.name);
+            if error.is_none() && unsaved && !sweep.unrecoverable.contains(&setting.name) {
+                sweep.unrecoverable.push(setting.name);
+            }
+            thread::sleep(settle);
+            let after = match snapshot(camera) {
+                Ok(after) => after,
+                Err(e) => {
+                    sweep.error = Some(e);
+                    break 'probe;
+                }
+            };
+            sweep.probes.push(Probe {
+                setting: format!("{} {}", setting.name, setting.format(value)),
+                error,
+                changes: diff(&before, &after),
+            });
+            before = after;
+        }
+    }
+
+    for (setting, value) in saved {
+        if let Err(e) = setting.set(camera, value) {
+            sweep.unrestored.push((setting.name, e));
+        }
+    }
+    Ok(sweep)
+}
+
+/// Turn a sweep into a map from (selector, offset) to the settings that
+/// changed that byte.
+pub fn byte_map(probes: &[Probe]) -> BTreeMap<(u8, usize), Vec<&str>> {
+    let mut map: BTreeMap<(u8, usize), Vec<&str>> = BTreeMap::new();
+    for probe in probes {
+        for change in &probe.changes {
+            map.entry((change.selector, change.offset))
+                .or_default()
+                .push(&probe.setting);
+        }
+    }
+    map
+}
@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! The effective field of view.
+//!
+//! What the camera sees depends on both the [`FOVMode`] and the zoom. The
+//! modes are rated by their diagonal field of view; a [`FovCalibration`]
+//! holds those ratings for a model, and turns a mode and a [`ZoomRatio`]
+//! into horizontal and vertical angles, and back. Both the modes and the
+//! zoom are crops of the sensor, so zooming divides the tangent of the
+//! half-angle by the ratio.
+
+use crate::{Angle, Error, FOVMode, ZoomRatio};
+
+/// Horizontal and vertical field of view, in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldOfView {
+    pub horizontal: f64,
+    pub vertical: f64,
+}
+
+/// The rated field of view of each [`FOVMode`] of a camera model, and how
+/// far it zooms.
+#[derive(Debug)]
+pub struct FovCalibration {
+    /// Matched against the V4L2 card name.
+    pub model: &'static str,
+    /// Diagonal field of view in degrees for Wide, Normal and Narrow.
+    pub diagonal: [f64; 3],
+    /// Width over height of the picture the ratings are for.
+    pub aspect: f64,
+    /// The magnification at the top of the zoom range.
+    pub max_zoom: f64,
+}
+
+/// Known models, most specific first.
+pub static CALIBRATIONS: [FovCalibration; 1] = [FovCalibration {
+    model: "OBSBOT Tiny 2",
+    diagonal: [86.0, 78.0, 65.0],
+    aspect: 16.0 / 9.0,
+    max_zoom: 4.0,
+}];
+
+/// The calibration for a camera whose V4L2 card name is `card`, or
+/// [`Error::NotCalibrated`] for models without one.
+pub fn calibration(card: &str) -> Result<&'static FovCalibration, Error> {
+    CALIBRATIONS
+        .iter()
+        .find(|c| card.contains(c.model))
+        .ok_or_else(|| Error::NotCalibrated(card.to_string()))
+}
+
+impl FieldOfView {
+    /// The pan and tilt that bring the point (`x`, `y`) of a `width` by
+    /// `height` frame showing this field of view to its centre. Positive pan
+    /// is to the right and positive tilt is up, as on the camera; `y` counts
+    /// down from the top.
+    pub fn offset_to(&self, x: f64, y: f64, width: f64, height: f64) -> (Angle, Angle) {
+        let angle = |pos: f64, size: f64, fov: f64| {
+            let t = (2.0 * pos / size - 1.0) * half_tan(fov);
+            Angle::from_degrees(t.atan().to_degrees())
+        };
+        let tilt = angle(y, height, self.vertical);
+        (
+            angle(x, width, self.horizontal),
+            Angle::from_arcsec(-tilt.arcsec()),
+        )
+    }
+}
+
+fn half_tan(degrees: f64) -> f64 {
+    (degrees.to_radians() / 2.0).tan()
+}
+
+fn from_half_tan(t: f64) -> f64 {
+    2.0 * t.atan().to_degrees()
+}
+
+impl FovCalibration {
+    /// The field of view in `mode` and zoomed by `zoom`.
+    pub fn at(&self, mode: FOVMode, zoom: ZoomRatio) -> FieldOfView {
+        let index = FOVMode::ALL.iter().position(|m| *m == mode).unwrap_or(0);
+        let diagonal = half_tan(self.diagonal[index]) / zoom.0.max(1.0);
+        let scale = diagonal / self.aspect.hypot(1.0);
+        FieldOfView {
+            horizontal: from_half_tan(scale * self.aspect),
+            vertical: from_half_tan(scale),
+        }
+    }
+
+    /// The horizontal field of view with the same framing as a vertical one
+    /// of `degrees`.
+    pub fn horizontal_for_vertical(&self, degrees: f64) -> f64 {
+        from_half_tan(half_tan(degrees) * self.aspect)
+    }
+
+    /// The mode and zoom that come closest to a horizontal field of view of
+    /// `degrees`: the narrowest mode that is at least that wide, zoomed in
+    /// to match. Targets outside what the camera can do get its widest or
+    /// narrowest view.
+    pub fn settings_for(&self, degrees: f64) -> (FOVMode, ZoomRatio) {
+        let widest = |mode| self.at(mode, ZoomRatio(1.0)).horizontal;
+        let mode = FOVMode::ALL
+            .into_iter()
+            .rev()
+            .find(|&mode| widest(mode) >= degrees)
+            .unwrap_or(FOVMode::ALL[0]);
+        let ratio = half_tan(widest(mode)) / half_tan(degrees.max(0.1));
+        (mode, ZoomRatio(ratio.clamp(1.0, self.max_zoom)))
+    }
+}
@@ -1,229 +1,760 @@
 // SPDX-License-Identifier: EUPL-1.2
 
+#[cfg(feature = "capture")]
+pub mod capture;
+#[cfg(feature = "trace")]
+pub mod explore;
+pub mod fov;
+pub mod limits;
+pub mod motion;
+pub mod pcap;
+#[cfg(feature = "profile")]
+pub mod preset;
+#[cfg(feature = "profile")]
+pub mod profile;
+pub mod ptz;
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod settings;
+pub mod sim;
+#[cfg(feature = "trace")]
+pub mod trace;
 mod usbio;
 
 use errno::Errno;
-use std::{fmt::Display, io, thread, time::Duration};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::{io, thread, time::Duration};
 use thiserror::Error;
+use tiny2_proto::command::{self, Request};
 use usbio::{UvcUsbIo, V4l2CtrlRange};
 
-const AUTO_EXP_CMD: [u8; 18] = [
-    0xaa, 0x25, 0x16, 0x00, 0x0c, 0x00, 0x58, 0x91, 0x0a, 0x02, 0x82, 0x29, 0x05, 0x00, 0xb2, 0xaf,
-    0x02, 0x04,
-];
-const MANUAL_EXP_CMD: [u8; 18] = [
-    0xaa, 0x25, 0x15, 0x00, 0x0c, 0x00, 0xa8, 0x9e, 0x0a, 0x02, 0x82, 0x29, 0x05, 0x00, 0xf9, 0x27,
-    0x01, 0x32,
-];
+pub use fov::{FieldOfView, FovCalibration};
+#[cfg(feature = "profile")]
+pub use limits::LimitStore;
+pub use limits::{Limit, SoftLimits};
+pub use ptz::{Angle, ZoomRatio};
+pub use tiny2_proto as proto;
+pub use tiny2_proto::{
+    AIMode, CameraStatus, ExposureMode, FOVMode, FocusMode, Framing, PowerLineFrequency,
+    TrackingMode, UnverifiedStatus,
+};
+pub use usbio::DeviceInfo;
+
+#[cfg(feature = "trace")]
+pub use explore::Explorer;
+#[cfg(feature = "profile")]
+pub use preset::{Preset, PresetStore};
+#[cfg(feature = "profile")]
+pub use profile::Profile;
+#[cfg(feature = "remote")]
+pub use remote::RemoteCamera;
+#[cfg(feature = "trace")]
+pub use trace::{Trace, TraceReplay};
+
+/// Serialize byte vectors as hex strings.
+#[cfg(feature = "serde")]
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        hex::decode(s).map_err(serde::de::Error::custom)
+    }
+}
 
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("value of {1} is not supported for {0}")]
     UnsupportedIntValue(String, i32),
+    #[error("\"{1}\" is not a valid {0}")]
+    UnsupportedName(String, String),
+    #[error("unknown {0} in status: {1:#04x} {2:#04x}")]
+    UnknownStatus(&'static str, u8, u8),
+    #[error("status block of {0} bytes is shorter than {1}")]
+    StatusTooShort(usize, usize),
+    #[error("frame body of {0} bytes is longer than 65535")]
+    FrameTooLong(usize),
     #[error("USB IO error: {0}")]
     USBIOError(i32),
     #[error("IO error: {0}")]
     IOError(#[from] io::Error),
-    #[error("Osc error: {0}")]
-    OscError(#[from] rosc::OscError),
+    #[error("profile format error: {0}")]
+    ProfileFormat(String),
+    #[error("{0} profile field(s) failed to apply")]
+    ProfileApply(usize),
+    #[error("{0} setting(s) could not be restored")]
+    SweepRestore(usize),
+    #[error("no preset named \"{0}\"")]
+    NoSuchPreset(String),
+    #[error("trace format error: {0}")]
+    TraceFormat(String),
+    #[error("capture format error: {0}")]
+    CaptureFormat(String),
+    #[error("daemon error: {0}")]
+    Remote(String),
     #[error("no camera found")]
     NoCameraFound,
+    #[error("{control} value {value} is outside {minimum}..={maximum}")]
+    OutOfRange {
+        control: &'static str,
+        value: i32,
+        minimum: i32,
+        maximum: i32,
+    },
+    #[error("soft limit for {0} has its minimum {1} above its maximum {2}")]
+    InvalidLimit(&'static str, i32, i32),
+    #[error("a move lasting {0:.3e} seconds is not possible")]
+    InvalidDuration(f64),
+    #[error("command of {0} bytes is longer than the {1}-byte buffer")]
+    CommandTooLong(usize, usize),
+    #[error("the video stream is in use by another application")]
+    StreamBusy,
+    #[error("image error: {0}")]
+    Image(String),
+    #[error("no field of view calibration for \"{0}\"")]
+    NotCalibrated(String),
+    #[error("the FOV mode isn't known; the camera can't report it until it is set")]
+    FovModeUnknown,
+    #[error("{0} uses unconfirmed command bytes; allow unverified commands to send it")]
+    Unverified(&'static str),
+    #[error("{0} isn't supported by this camera")]
+    Unsupported(&'static str),
+}
+
+impl From<tiny2_proto::Error> for Error {
+    fn from(e: tiny2_proto::Error) -> Self {
+        match e {
+            tiny2_proto::Error::UnsupportedIntValue(t, v) => Error::UnsupportedIntValue(t, v),
+            tiny2_proto::Error::UnsupportedName(t, s) => Error::UnsupportedName(t, s),
+            tiny2_proto::Error::UnknownStatus(f, m, n) => Error::UnknownStatus(f, m, n),
+            tiny2_proto::Error::StatusTooShort(n, len) => Error::StatusTooShort(n, len),
+            tiny2_proto::Error::FrameTooLong(n) => Error::FrameTooLong(n),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct Camera {
-    handle: usbio::CameraHandle,
+    handle: Arc<usbio::CameraHandleType>,
     verbose: bool,
+    /// The device this camera was opened from, if it is one.
+    device: Option<DeviceInfo>,
+    /// Clamp out-of-range PTZ and focus values instead of rejecting them.
+    clamp: bool,
+    /// Send selector 0x06 commands whose bytes are guesses.
+    unverified: bool,
+    limits: Mutex<SoftLimits>,
+    /// The FOV mode last set, which the device can't report.
+    fov_mode: Mutex<Option<FOVMode>>,
+    /// The exposure mode last set, which the device can't report either.
+    exposure_mode: Mutex<Option<ExposureMode>>,
+    /// Control ranges by V4L2 id, queried once.
+    ranges: Mutex<BTreeMap<u32, CtrlRange>>,
+    /// Continuous movement on axes without a V4L2 speed control.
+    jog: Mutex<Option<motion::Jog>>,
 }
 
-pub struct CameraStatus {
-    pub ai_mode: AIMode,
-    pub hdr_on: bool,
-}
+pub trait OBSBotWebCam {
+    fn set_ai_mode(&self, mode: AIMode) -> Result<(), Error>;
+    fn get_ai_mode(&self) -> Result<AIMode, Error>;
+    fn set_hdr_mode(&self, mode: bool) -> Result<(), Error>;
+    fn set_exposure_mode(&self, mode: ExposureMode) -> Result<(), Error>;
+    /// Get the exposure mode last set through this camera. The device can't
+    /// report it, so this is `None` until one is set.
+    fn get_exposure_mode(&self) -> Result<Option<ExposureMode>, Error>;
+    fn set_fov(&self, mode: FOVMode) -> Result<(), Error>;
+    /// Get the FOV mode last set through this camera. The device can't
+    /// report it, so this is `None` until one is set.
+    fn get_fov_mode(&self) -> Result<Option<FOVMode>, Error>;
+    /// Set the tracking framing (headroom) and responsiveness.
+    fn set_tracking_mode(&self, mode: TrackingMode) -> Result<(), Error>;
+    /// Enable or disable automatic zoom while tracking.
+    fn set_auto_zoom(&self, on: bool) -> Result<(), Error>;
+    /// Lock tracking onto the currently selected person.
+    fn set_target_lock(&self, on: bool) -> Result<(), Error>;
+    /// Read and decode the 0x06 status block.
+    fn get_status(&self) -> Result<CameraStatus, Error>;
 
-impl CameraStatus {
-    pub fn decode(bytes: &[u8]) -> Self {
-        let m = bytes[0x18];
-        let n = bytes[0x1c];
-
-        let ai_mode = match (m, n) {
-            (0, 0) => AIMode::NoTracking,
-            (2, 0) => AIMode::NormalTracking,
-            (2, 1) => AIMode::UpperBody,
-            (2, 2) => AIMode::CloseUp,
-            (2, 3) => AIMode::Headless,
-            (2, 4) => AIMode::LowerBody,
-            (5, 0) => AIMode::DeskMode,
-            (4, 0) => AIMode::Whiteboard,
-            (6, 0) => AIMode::Hand,
-            (1, 0) => AIMode::Group,
-            (_, _) => panic!(),
-        };
+    // ---- Image controls ----
+
+    /// Get the current anti-flicker power line frequency setting.
+    fn get_power_line_frequency(&self) -> Result<PowerLineFrequency, Error>;
+    /// Set the anti-flicker power line frequency.
+    fn set_power_line_frequency(&self, freq: PowerLineFrequency) -> Result<(), Error>;
+    /// Get whether the image is mirrored horizontally.
+    fn get_mirror(&self) -> Result<bool, Error>;
+    /// Mirror the image horizontally.
+    fn set_mirror(&self, on: bool) -> Result<(), Error>;
+    /// Get whether the image is flipped vertically.
+    fn get_flip(&self) -> Result<bool, Error>;
+    /// Flip the image vertically.
+    fn set_flip(&self, on: bool) -> Result<(), Error>;
+    /// Get the image brightness.
+    fn get_brightness(&self) -> Result<i32, Error>;
+    /// Set the image brightness.
+    fn set_brightness(&self, value: i32) -> Result<(), Error>;
+    /// Query the supported range for brightness.
+    fn query_brightness_range(&self) -> Result<CtrlRange, Error>;
+    /// Get the image contrast.
+    fn get_contrast(&self) -> Result<i32, Error>;
+    /// Set the image contrast.
+    fn set_contrast(&self, value: i32) -> Result<(), Error>;
+    /// Query the supported range for contrast.
+    fn query_contrast_range(&self) -> Result<CtrlRange, Error>;
+    /// Get the color saturation.
+    fn get_saturation(&self) -> Result<i32, Error>;
+    /// Set the color saturation.
+    fn set_saturation(&self, value: i32) -> Result<(), Error>;
+    /// Query the supported range for saturation.
+    fn query_saturation_range(&self) -> Result<CtrlRange, Error>;
+    /// Get the image sharpness.
+    fn get_sharpness(&self) -> Result<i32, Error>;
+    /// Set the image sharpness.
+    fn set_sharpness(&self, value: i32) -> Result<(), Error>;
+    /// Query the supported range for sharpness.
+    fn query_sharpness_range(&self) -> Result<CtrlRange, Error>;
+
+    // ---- Focus ----
 
-        let hdr_on = bytes[0x6] != 0;
+    /// Get the current focus mode, Auto or Manual, from the V4L2
+    /// `focus_automatic_continuous` control. Face priority is never reported,
+    /// since it would be read from an unverified offset.
+    fn get_focus_mode(&self) -> Result<FocusMode, Error>;
+    /// Set the focus mode. Auto and Manual only change the V4L2
+    /// `focus_automatic_continuous` control; Face also sends the unverified
+    /// [`settings::FACE_FOCUS`] command, which switching back doesn't undo.
+    fn set_focus_mode(&self, mode: FocusMode) -> Result<(), Error>;
+    /// Get the current absolute focus value.
+    fn get_focus(&self) -> Result<i32, Error>;
+    /// Set the absolute focus value. Only takes effect in manual focus mode.
+    fn set_focus(&self, value: i32) -> Result<(), Error>;
+    /// Query the supported range for focus (absolute).
+    fn query_focus_range(&self) -> Result<CtrlRange, Error>;
 
-        CameraStatus { ai_mode, hdr_on }
+    // ---- Pan/Tilt/Zoom ----
+
+    /// Get the current absolute pan value (in arc-seconds).
+    fn get_pan(&self) -> Result<i32, Error>;
+    /// Set the absolute pan value (in arc-seconds).
+    fn set_pan(&self, value: i32) -> Result<(), Error>;
+    /// Get the current absolute tilt value (in arc-seconds).
+    fn get_tilt(&self) -> Result<i32, Error>;
+    /// Set the absolute tilt value (in arc-seconds).
+    fn set_tilt(&self, value: i32) -> Result<(), Error>;
+    /// Get the current absolute zoom value.
+    fn get_zoom(&self) -> Result<i32, Error>;
+    /// Set the absolute zoom value.
+    fn set_zoom(&self, value: i32) -> Result<(), Error>;
+    /// Move pan by a relative amount. Within soft limits this is done as
+    /// an absolute move.
+    fn pan_relative(&self, delta: i32) -> Result<(), Error>;
+    /// Move tilt by a relative amount.
+    fn tilt_relative(&self, delta: i32) -> Result<(), Error>;
+    /// Move zoom by a relative amount.
+    fn zoom_relative(&self, delta: i32) -> Result<(), Error>;
+    /// Query the supported range for pan (absolute).
+    fn query_pan_range(&self) -> Result<CtrlRange, Error>;
+    /// Query the supported range for tilt (absolute).
+    fn query_tilt_range(&self) -> Result<CtrlRange, Error>;
+    /// Query the supported range for zoom (absolute).
+    fn query_zoom_range(&self) -> Result<CtrlRange, Error>;
+    /// The field of view ratings for this camera's model, or
+    /// [`Error::NotCalibrated`] if there are none.
+    fn fov_calibration(&self) -> Result<&'static FovCalibration, Error>;
+    /// Get the effective field of view from the FOV mode and zoom. Fails
+    /// with [`Error::FovModeUnknown`] until the FOV mode has been set.
+    fn get_field_of_view(&self) -> Result<FieldOfView, Error> {
+        let mode = self.get_fov_mode()?.ok_or(Error::FovModeUnknown)?;
+        Ok(self.fov_calibration()?.at(mode, self.get_zoom_ratio()?))
+    }
+    /// Set the FOV mode and zoom for a horizontal field of view in degrees,
+    /// as near as the camera can get. Returns the field of view set.
+    fn set_horizontal_fov(&self, degrees: f64) -> Result<FieldOfView, Error> {
+        let (mode, zoom) = self.fov_calibration()?.settings_for(degrees);
+        self.set_fov(mode)?;
+        self.set_zoom_ratio(zoom)?;
+        self.get_field_of_view()
+    }
+    /// Set the FOV mode and zoom for a vertical field of view in degrees.
+    fn set_vertical_fov(&self, degrees: f64) -> Result<FieldOfView, Error> {
+        self.set_horizontal_fov(self.fov_calibration()?.horizontal_for_vertical(degrees))
     }
-}
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum AIMode {
-    NoTracking,
-    NormalTracking,
-    UpperBody,
-    CloseUp,
-    Headless,
-    LowerBody,
-    DeskMode,
-    Whiteboard,
-    Hand,
-    Group,
-}
+    /// Pan and tilt so that the point (`x`, `y`) of a `frame_w` by `frame_h`
+    /// picture from the camera ends up in the middle, using the effective
+    /// field of view. Goes only as far as the soft limits allow.
+    fn center_on(&self, x: f64, y: f64, frame_w: f64, frame_h: f64) -> Result<(), Error> {
+        if !(frame_w > 0.0 && frame_h > 0.0) {
+            return Err(Error::UnsupportedName(
+                "frame size".to_string(),
+                format!("{}x{}", frame_w, frame_h),
+            ));
+        }
+        let fov = self.get_field_of_view()?;
+        let (pan, tilt) = fov.offset_to(x, y, frame_w, frame_h);
+        let limits = self.get_soft_limits()?;
+        let pan_range = limits.pan.restrict(&self.query_pan_range()?);
+        let tilt_range = limits.tilt.restrict(&self.query_tilt_range()?);
+        self.set_pan(pan_range.clamp(self.get_pan()?.saturating_add(pan.arcsec())))?;
+        self.set_tilt(tilt_range.clamp(self.get_tilt()?.saturating_add(tilt.arcsec())))
+    }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum ExposureMode {
-    Manual,
-    Global,
-    Face,
+    /// Get the soft limits that absolute, relative and continuous moves are
+    /// kept within.
+    fn get_soft_limits(&self) -> Result<SoftLimits, Error>;
+    /// Replace the soft limits and save them for this camera. Stops any
+    /// continuous movement.
+    fn set_soft_limits(&self, limits: SoftLimits) -> Result<(), Error>;
+
+    /// Get the current pan angle.
+    fn get_pan_angle(&self) -> Result<Angle, Error> {
+        self.get_pan().map(Angle::from_arcsec)
+    }
+    /// Set the pan angle.
+    fn set_pan_angle(&self, angle: Angle) -> Result<(), Error> {
+        self.set_pan(angle.arcsec())
+    }
+    /// Get the current tilt angle.
+    fn get_tilt_angle(&self) -> Result<Angle, Error> {
+        self.get_tilt().map(Angle::from_arcsec)
+    }
+    /// Set the tilt angle.
+    fn set_tilt_angle(&self, angle: Angle) -> Result<(), Error> {
+        self.set_tilt(angle.arcsec())
+    }
+    /// Get the current zoom as a magnification.
+    fn get_zoom_ratio(&self) -> Result<ZoomRatio, Error> {
+        let range = self.query_zoom_range()?;
+        let max_zoom = self.fov_calibration()?.max_zoom;
+        Ok(ZoomRatio::from_ctrl(self.get_zoom()?, &range, max_zoom))
+    }
+    /// Set the zoom as a magnification.
+    fn set_zoom_ratio(&self, ratio: ZoomRatio) -> Result<(), Error> {
+        let range = self.query_zoom_range()?;
+        self.set_zoom(ratio.to_ctrl(&range, self.fov_calibration()?.max_zoom))
+    }
+
+    /// Keep pan, tilt and zoom moving until told otherwise. Speeds are
+    /// fractions of full speed from -1 to 1: positive pans right, tilts up
+    /// and zooms in, and zero holds the axis still.
+    fn move_continuous(
+        &self,
+        pan_speed: f64,
+        tilt_speed: f64,
+        zoom_speed: f64,
+    ) -> Result<(), Error>;
+    /// Stop any continuous movement.
+    fn stop(&self) -> Result<(), Error> {
+        self.move_continuous(0.0, 0.0, 0.0)
+    }
+
+    // ---- Raw extension-unit access ----
+
+    /// Send a raw command to an extension-unit selector. It is padded with
+    /// zeros to the 60-byte buffer, and can't be longer.
+    fn send_cmd(&self, unit: u8, selector: u8, cmd: &[u8]) -> Result<(), Error>;
+    /// Read the raw 60-byte GET_CUR buffer for an extension-unit selector.
+    fn read_selector(&self, selector: u8) -> Result<[u8; 60], Error>;
+
+    /// Read the status block and every available V4L2 control.
+    fn get_state(&self) -> Result<CameraState, Error> {
+        Ok(CameraState {
+            status: self.get_status()?,
+            power_line_frequency: self.get_power_line_frequency().ok(),
+            focus_mode: self.get_focus_mode().ok(),
+            focus: self.get_focus().ok(),
+            mirror: self.get_mirror().ok(),
+            flip: self.get_flip().ok(),
+            pan: self.get_pan().ok(),
+            tilt: self.get_tilt().ok(),
+            zoom: self.get_zoom().ok(),
+            pan_range: self.query_pan_range().ok(),
+            tilt_range: self.query_tilt_range().ok(),
+            zoom_range: self.query_zoom_range().ok(),
+            focus_range: self.query_focus_range().ok(),
+        })
+    }
 }
 
-impl Display for AIMode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            AIMode::NoTracking => write!(f, "No Tracking"),
-            AIMode::NormalTracking => write!(f, "Normal Tracking"),
-            AIMode::UpperBody => write!(f, "Upper Body"),
-            AIMode::CloseUp => write!(f, "Close-up"),
-            AIMode::Headless => write!(f, "Headless"),
-            AIMode::LowerBody => write!(f, "Lower Body"),
-            AIMode::DeskMode => write!(f, "Desk Mode"),
-            AIMode::Whiteboard => write!(f, "Whiteboard"),
-            AIMode::Hand => write!(f, "Hand"),
-            AIMode::Group => write!(f, "Group"),
+impl OBSBotWebCam for Camera {
+    fn set_fov(&self, mode: FOVMode) -> Result<(), Error> {
+        self.send(command::fov(mode))?;
+        *self.fov_mode.lock().expect("FOV mode lock poisoned") = Some(mode);
+        Ok(())
+    }
+
+    fn get_fov_mode(&self) -> Result<Option<FOVMode>, Error> {
+        Ok(*self.fov_mode.lock().expect("FOV mode lock poisoned"))
+    }
+
+    fn fov_calibration(&self) -> Result<&'static FovCalibration, Error> {
+        // Simulated and replayed cameras have no card name; they stand in
+        // for the model the simulator imitates.
+        fov::calibration(self.device.as_ref().map_or(sim::MODEL, |d| d.card.as_str()))
+    }
+    fn set_ai_mode(&self, mode: AIMode) -> Result<(), Error> {
+        self.send(command::ai_mode(mode))
+    }
+
+    fn set_exposure_mode(&self, mode: ExposureMode) -> Result<(), Error> {
+        for request in command::exposure_mode(mode) {
+            self.send(request)?;
         }
+        *self
+            .exposure_mode
+            .lock()
+            .expect("exposure mode lock poisoned") = Some(mode);
+        Ok(())
+    }
+
+    fn get_exposure_mode(&self) -> Result<Option<ExposureMode>, Error> {
+        Ok(*self
+            .exposure_mode
+            .lock()
+            .expect("exposure mode lock poisoned"))
+    }
+
+    fn set_hdr_mode(&self, mode: bool) -> Result<(), Error> {
+        self.send(command::hdr(mode))
+    }
+
+    fn get_ai_mode(&self) -> Result<AIMode, Error> {
+        Ok(CameraStatus::decode_ai_mode(&self.read_selector(0x6)?)?)
+    }
+
+    fn set_tracking_mode(&self, mode: TrackingMode) -> Result<(), Error> {
+        self.send(command::tracking_mode(mode))
+    }
+
+    fn set_auto_zoom(&self, on: bool) -> Result<(), Error> {
+        self.send(command::auto_zoom(on))
+    }
+
+    fn set_target_lock(&self, on: bool) -> Result<(), Error> {
+        self.send(command::target_lock(on))
+    }
+
+    fn get_status(&self) -> Result<CameraStatus, Error> {
+        let mut data: [u8; 60] = [0u8; 60];
+        self.get_cur(0x2, 0x6, &mut data)
+            .map_err(|x| Error::USBIOError(x.0))?;
+        Ok(CameraStatus::decode(&data)?)
+    }
+
+    fn get_power_line_frequency(&self) -> Result<PowerLineFrequency, Error> {
+        self.handle
+            .get_ctrl(usbio::V4L2_CID_POWER_LINE_FREQUENCY)
+            .map_err(|e| Error::USBIOError(e.0))?
+            .try_into()
+            .map_err(Error::from)
+    }
+
+    fn set_power_line_frequency(&self, freq: PowerLineFrequency) -> Result<(), Error> {
+        self.handle
+            .set_ctrl(usbio::V4L2_CID_POWER_LINE_FREQUENCY, freq.to_ctrl_value())
+            .map_err(|e| Error::USBIOError(e.0))
+    }
+
+    // Mirror and flip are only the V4L2 hflip/vflip controls; the extension
+    // unit bytes for them are unconfirmed.
+    fn get_mirror(&self) -> Result<bool, Error> {
+        self.require_ctrl(usbio::V4L2_CID_HFLIP, "mirror")?;
+        self.handle
+            .get_ctrl(usbio::V4L2_CID_HFLIP)
+            .map(|v| v != 0)
+            .map_err(|e| Error::USBIOError(e.0))
+    }
+
+    fn set_mirror(&self, on: bool) -> Result<(), Error> {
+        self.require_ctrl(usbio::V4L2_CID_HFLIP, "mirror")?;
+        self.handle
+            .set_ctrl(usbio::V4L2_CID_HFLIP, on.into())
+            .map_err(|e| Error::USBIOError(e.0))
+    }
+
+    fn get_flip(&self) -> Result<bool, Error> {
+        self.require_ctrl(usbio::V4L2_CID_VFLIP, "flip")?;
+        self.handle
+            .get_ctrl(usbio::V4L2_CID_VFLIP)
+            .map(|v| v != 0)
+            .map_err(|e| Error::USBIOError(e.0))
+    }
+
+    fn set_flip(&self, on: bool) -> Result<(), Error> {
+        self.require_ctrl(usbio::V4L2_CID_VFLIP, "flip")?;
+        self.handle
+            .set_ctrl(usbio::V4L2_CID_VFLIP, on.into())
+            .map_err(|e| Error::USBIOError(e.0))
+    }
+
+    fn get_brightness(&self) -> Result<i32, Error> {
+        self.handle
+            .get_ctrl(usbio::V4L2_CID_BRIGHTNESS)
+            .map_err(|e| Error::USBIOError(e.0))
+    }
+
+    fn set_brightness(&self, value: i32) -> Result<(), Error> {
+        self.set_ranged(usbio::V4L2_CID_BRIGHTNESS, "brightness", value)
+    }
+
+    fn query_brightness_range(&self) -> Result<CtrlRange, Error> {
+        self.ctrl_range(usbio::V4L2_CID_BRIGHTNESS)
+    }
+
+    fn get_contrast(&self) -> Result<i32, Error> {
+        self.handle
+            .get_ctrl(usbio::V4L2_CID_CONTRAST)
+            .map_err(|e| Error::USBIOError(e.0))
+    }
+
+    fn set_contrast(&self, value: i32) -> Result<(), Error> {
+        self.set_ranged(usbio::V4L2_CID_CONTRAST, "contrast", value)
+    }
+
+    fn query_contrast_range(&self) -> Result<CtrlRange, Error> {
+        self.ctrl_range(usbio::V4L2_CID_CONTRAST)
+    }
+
+    fn get_saturation(&self) -> Result<i32, Error> {
+        self.handle
+            .get_ctrl(usbio::V4L2_CID_SATURATION)
+            .map_err(|e| Error::USBIOError(e.0))
     }
-}
 
-impl TryFrom<i32> for AIMode {
-    type Error = Error;
-
-    fn try_from(value: i32) -> Result<Self, Self::Error> {
-        match value {
-            0 => Ok(AIMode::NoTracking),
-            1 => Ok(AIMode::NormalTracking),
-            2 => Ok(AIMode::UpperBody),
-            3 => Ok(AIMode::CloseUp),
-            4 => Ok(AIMode::Headless),
-            5 => Ok(AIMode::LowerBody),
-            6 => Ok(AIMode::DeskMode),
-            7 => Ok(AIMode::Whiteboard),
-            8 => Ok(AIMode::Hand),
-            9 => Ok(AIMode::Group),
-            _ => Err(Error::UnsupportedIntValue("AIMode".to_string(), value)),
+    fn set_saturation(&self, value: i32) -> Result<(), Error> {
+        self.set_ranged(usbio::V4L2_CID_SATURATION, "saturation", value)
+    }
+
+    fn query_saturation_range(&self) -> Result<CtrlRange, Error> {
+        self.ctrl_range(usbio::V4L2_CID_SATURATION)
+    }
+
+    fn get_sharpness(&self) -> Result<i32, Error> {
+        self.handle
+            .get_ctrl(usbio::V4L2_CID_SHARPNESS)
+            .map_err(|e| Error::USBIOError(e.0))
+    }
+
+    fn set_sharpness(&self, value: i32) -> Result<(), Error> {
+        self.set_ranged(usbio::V4L2_CID_SHARPNESS, "sharpness", value)
+    }
+
+    fn query_sharpness_range(&self) -> Result<CtrlRange, Error> {
+        self.ctrl_range(usbio::V4L2_CID_SHARPNESS)
+    }
+
+    fn get_focus_mode(&self) -> Result<FocusMode, Error> {
+        let auto = self
+            .handle
+            .get_ctrl(usbio::V4L2_CID_FOCUS_AUTO)
+            .map_err(|e| Error::USBIOError(e.0))?;
+        if auto == 0 {
+            Ok(FocusMode::Manual)
+        } else {
+            Ok(FocusMode::Auto)
         }
     }
-}
 
-pub enum TrackingMode {
-    Headroom,
-    Standard,
-    Motion,
-}
+    fn set_focus_mode(&self, mode: FocusMode) -> Result<(), Error> {
+        let auto = if mode == FocusMode::Manual { 0 } else { 1 };
+        self.handle
+            .set_ctrl(usbio::V4L2_CID_FOCUS_AUTO, auto)
+            .map_err(|e| Error::USBIOError(e.0))?;
+        if mode == FocusMode::Face {
+            self.send(command::face_focus(true))?;
+        }
+        Ok(())
+    }
 
-impl TryFrom<i32> for TrackingMode {
-    type Error = Error;
+    fn get_focus(&self) -> Result<i32, Error> {
+        self.handle
+            .get_ctrl(usbio::V4L2_CID_FOCUS_ABSOLUTE)
+            .map_err(|e| Error::USBIOError(e.0))
+    }
 
-    fn try_from(value: i32) -> Result<Self, Self::Error> {
-        match value {
-            0 => Ok(TrackingMode::Headroom),
-            1 => Ok(TrackingMode::Standard),
-            2 => Ok(TrackingMode::Motion),
-            _ => Err(Error::UnsupportedIntValue(
-                "TrackingMode".to_string(),
-                value,
-            )),
+    fn set_focus(&self, value: i32) -> Result<(), Error> {
+        self.set_ranged(usbio::V4L2_CID_FOCUS_ABSOLUTE, "focus", value)
+    }
+
+    fn query_focus_range(&self) -> Result<CtrlRange, Error> {
+        self.ctrl_range(usbio::V4L2_CID_FOCUS_ABSOLUTE)
+    }
+
+    fn get_pan(&self) -> Result<i32, Error> {
+        self.handle
+            .get_ctrl(usbio::V4L2_CID_PAN_ABSOLUTE)
+            .map_err(|e| Error::USBIOError(e.0))
+    }
+
+    fn set_pan(&self, value: i32) -> Result<(), Error> {
+        self.set_ranged(usbio::V4L2_CID_PAN_ABSOLUTE, "pan", value)
+    }
+
+    fn get_tilt(&self) -> Result<i32, Error> {
+        self.handle
+            .get_ctrl(usbio::V4L2_CID_TILT_ABSOLUTE)
+            .map_err(|e| Error::USBIOError(e.0))
+    }
+
+    fn set_tilt(&self, value: i32) -> Result<(), Error> {
+        self.set_ranged(usbio::V4L2_CID_TILT_ABSOLUTE, "tilt", value)
+    }
+
+    fn get_zoom(&self) -> Result<i32, Error> {
+        self.handle
+            .get_ctrl(usbio::V4L2_CID_ZOOM_ABSOLUTE)
+            .map_err(|e| Error::USBIOError(e.0))
+    }
+
+    fn set_zoom(&self, value: i32) -> Result<(), Error> {
+        self.set_ranged(usbio::V4L2_CID_ZOOM_ABSOLUTE, "zoom", value)
+    }
+
+    fn pan_relative(&self, delta: i32) -> Result<(), Error> {
+        if self.limit(usbio::V4L2_CID_PAN_ABSOLUTE).is_unbounded() {
+            self.handle
+                .set_ctrl(usbio::V4L2_CID_PAN_RELATIVE, delta)
+                .map_err(|e| Error::USBIOError(e.0))
+        } else {
+            self.set_pan(self.get_pan()?.saturating_add(delta))
         }
     }
-}
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum FOVMode {
-    Wide,   // 86°
-    Normal, // 78°
-    Narrow, // 65°
-}
+    fn tilt_relative(&self, delta: i32) -> Result<(), Error> {
+        if self.limit(usbio::V4L2_CID_TILT_ABSOLUTE).is_unbounded() {
+            self.handle
+                .set_ctrl(usbio::V4L2_CID_TILT_RELATIVE, delta)
+                .map_err(|e| Error::USBIOError(e.0))
+        } else {
+            self.set_tilt(self.get_tilt()?.saturating_add(delta))
+        }
+    }
 
-impl FOVMode {
-    fn to_cmd_value(&self) -> u8 {
-        match self {
-            FOVMode::Wide => 1,
-            FOVMode::Normal => 2,
-            FOVMode::Narrow => 3,
+    fn zoom_relative(&self, delta: i32) -> Result<(), Error> {
+        if self.limit(usbio::V4L2_CID_ZOOM_ABSOLUTE).is_unbounded() {
+            self.handle
+                .set_ctrl(usbio::V4L2_CID_ZOOM_RELATIVE, delta)
+                .map_err(|e| Error::USBIOError(e.0))
+        } else {
+            self.set_zoom(self.get_zoom()?.saturating_add(delta))
         }
     }
-}
 
-pub trait OBSBotWebCam {
-    fn set_ai_mode(&self, mode: AIMode) -> Result<(), Error>;
-    fn get_ai_mode(&self) -> Result<AIMode, Error>;
-    fn set_hdr_mode(&self, mode: bool) -> Result<(), Error>;
-    fn set_exposure_mode(&self, mode: ExposureMode) -> Result<(), Error>;
-    fn set_fov(&self, mode: FOVMode) -> Result<(), Error>;
-}
+    fn query_pan_range(&self) -> Result<CtrlRange, Error> {
+        self.ctrl_range(usbio::V4L2_CID_PAN_ABSOLUTE)
+    }
 
-impl OBSBotWebCam for Camera {
-    fn set_fov(&self, mode: FOVMode) -> Result<(), Error> {
-        self.send_cmd(0x2, 0x6, &[0x04, 0x01, mode.to_cmd_value()])
+    fn query_tilt_range(&self) -> Result<CtrlRange, Error> {
+        self.ctrl_range(usbio::V4L2_CID_TILT_ABSOLUTE)
     }
-    fn set_ai_mode(&self, mode: AIMode) -> Result<(), Error> {
-        let cmd = match mode {
-            AIMode::NoTracking => [0x16, 0x02, 0x00, 0x00],
-            AIMode::NormalTracking => [0x16, 0x02, 0x02, 0x00],
-            AIMode::UpperBody => [0x16, 0x02, 0x02, 0x01],
-            AIMode::DeskMode => [0x16, 0x02, 0x05, 0x00],
-            AIMode::Whiteboard => [0x16, 0x02, 0x04, 0x00],
-            AIMode::Group => [0x16, 0x02, 0x01, 0x00],
-            AIMode::Hand => [0x16, 0x02, 0x03, 0x00],
-            AIMode::CloseUp => [0x16, 0x02, 0x02, 0x02],
-            AIMode::Headless => [0x16, 0x02, 0x02, 0x03],
-            AIMode::LowerBody => [0x16, 0x02, 0x02, 0x04],
-        };
-        self.send_cmd(0x2, 0x6, &cmd)
+
+    fn query_zoom_range(&self) -> Result<CtrlRange, Error> {
+        self.ctrl_range(usbio::V4L2_CID_ZOOM_ABSOLUTE)
     }
 
-    fn set_exposure_mode(&self, mode: ExposureMode) -> Result<(), Error> {
-        match mode {
-            ExposureMode::Manual => {
-                self.send_cmd(0x2, 0x2, &MANUAL_EXP_CMD)?;
+    fn get_soft_limits(&self) -> Result<SoftLimits, Error> {
+        Ok(*self.limits.lock().expect("limits lock poisoned"))
+    }
+
+    fn set_soft_limits(&self, limits: SoftLimits) -> Result<(), Error> {
+        limits.validate()?;
+        self.stop()?;
+        *self.limits.lock().expect("limits lock poisoned") = limits;
+        #[cfg(feature = "profile")]
+        if let Some(device) = &self.device {
+            let path = LimitStore::default_path();
+            let mut store = LimitStore::load(&path)?;
+            store.set(device, limits);
+            store.save(&path)?;
+        }
+        Ok(())
+    }
+
+    /// Uses the V4L2 `pan_speed`, `tilt_speed` and `zoom_continuous` controls
+    /// where the device has them, and steps the absolute controls on a
+    /// timer for the axes where it doesn't or that have soft limits.
+    fn move_continuous(
+        &self,
+        pan_speed: f64,
+        tilt_speed: f64,
+        zoom_speed: f64,
+    ) -> Result<(), Error> {
+        let speed_controls = [
+            usbio::V4L2_CID_PAN_SPEED,
+            usbio::V4L2_CID_TILT_SPEED,
+            usbio::V4L2_CID_ZOOM_CONTINUOUS,
+        ];
+        let position_controls = [
+            usbio::V4L2_CID_PAN_ABSOLUTE,
+            usbio::V4L2_CID_TILT_ABSOLUTE,
+            usbio::V4L2_CID_ZOOM_ABSOLUTE,
+        ];
+        let mut stepped = [0.0; 3];
+        let mut ranges = [None; 3];
+        for (i, speed) in [pan_speed, tilt_speed, zoom_speed].into_iter().enumerate() {
+            let speed = speed.clamp(-1.0, 1.0);
+            match self.ctrl_range(position_controls[i]) {
+                Ok(range) => ranges[i] = Some((range, self.limit(position_controls[i]))),
+                Err(e) if speed != 0.0 => return Err(e),
+                Err(_) => {}
             }
-            ExposureMode::Global => {
-                self.send_cmd(0x2, 0x2, &AUTO_EXP_CMD)?;
-                self.send_cmd(0x2, 0x6, &[0x03, 0x01, 0x00])?;
+            let native = if self.limit(position_controls[i]).is_unbounded() {
+                self.handle.query_ctrl(speed_controls[i]).ok()
+            } else {
+                None
+            };
+            match native {
+                Some(range) => self
+                    .handle
+                    .set_ctrl(speed_controls[i], motion::native_speed(speed, range))
+                    .map_err(|e| Error::USBIOError(e.0))?,
+                None => stepped[i] = speed,
             }
-            ExposureMode::Face => {
-                self.send_cmd(0x2, 0x2, &AUTO_EXP_CMD)?;
-                self.send_cmd(0x2, 0x6, &[0x03, 0x01, 0x01])?;
+        }
+
+        let mut jog = self.jog.lock().expect("jog lock poisoned");
+        let stopping = stepped.iter().all(|&s| s == 0.0);
+        if let Some(running) = jog.take() {
+            if !stopping && !running.is_finished() {
+                running.set_speeds(stepped);
+                *jog = Some(running);
+                return Ok(());
             }
-        };
+            running.stop()?;
+        }
+        if !stopping {
+            *jog = Some(motion::Jog::start(self.handle.clone(), ranges, stepped)?);
+        }
         Ok(())
     }
 
-    fn set_hdr_mode(&self, mode: bool) -> Result<(), Error> {
-        let cmd = if mode {
-            [0x01, 0x01, 0x01]
-        } else {
-            [0x01, 0x01, 0x00]
-        };
-        self.send_cmd(0x2, 0x6, &cmd)
+    fn send_cmd(&self, unit: u8, selector: u8, cmd: &[u8]) -> Result<(), Error> {
+        let mut data = [0u8; tiny2_proto::BUFFER_LEN];
+        if cmd.len() > data.len() {
+            return Err(Error::CommandTooLong(cmd.len(), data.len()));
+        }
+        data[..cmd.len()].copy_from_slice(cmd);
+
+        self.set_cur(unit, selector, &mut data)
+            .map_err(|e| Error::USBIOError(e.0))
     }
 
-    fn get_ai_mode(&self) -> Result<AIMode, Error> {
-        Ok(self.get_status()?.ai_mode)
+    fn read_selector(&self, selector: u8) -> Result<[u8; 60], Error> {
+        let mut data: [u8; 60] = [0u8; 60];
+        self.get_cur(0x2, selector, &mut data)
+            .map_err(|x| Error::USBIOError(x.0))?;
+        Ok(data)
     }
 }
 
 /// Range information for a V4L2 control (min, max, step, default).
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CtrlRange {
     pub minimum: i32,
     pub maximum: i32,
@@ -231,6 +762,65 @@ pub struct CtrlRange {
     pub default_value: i32,
 }
 
+impl CtrlRange {
+    pub fn contains(&self, value: i32) -> bool {
+        (self.minimum..=self.maximum).contains(&value)
+    }
+
+    pub fn clamp(&self, value: i32) -> i32 {
+        value.clamp(self.minimum, self.maximum)
+    }
+
+    /// The nearest value the control accepts: a whole number of steps
+    /// from the minimum, within the range.
+    pub fn snap(&self, value: i32) -> i32 {
+        let (min, max) = (self.minimum as i64, self.maximum as i64);
+        let step = self.step.max(1) as i64;
+        let value = (value as i64).clamp(min, max);
+        let steps = ((value - min) as f64 / step as f64).round() as i64;
+        let mut snapped = min + steps * step;
+        // The maximum needn't be a whole number of steps from the minimum.
+        if snapped > max {
+            snapped -= step;
+        }
+        snapped.clamp(min, max) as i32
+    }
+
+    /// `value` if it is in range, or [`Error::OutOfRange`] naming `control`.
+    pub fn check(&self, control: &'static str, value: i32) -> Result<i32, Error> {
+        if self.contains(value) {
+            Ok(value)
+        } else {
+            Err(Error::OutOfRange {
+                control,
+                value,
+                minimum: self.minimum,
+                maximum: self.maximum,
+            })
+        }
+    }
+}
+
+/// A snapshot of everything that can be read back from a camera. V4L2
+/// controls the device doesn't expose are `None`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CameraState {
+    pub status: CameraStatus,
+    pub power_line_frequency: Option<PowerLineFrequency>,
+    pub focus_mode: Option<FocusMode>,
+    pub focus: Option<i32>,
+    pub mirror: Option<bool>,
+    pub flip: Option<bool>,
+    pub pan: Option<i32>,
+    pub tilt: Option<i32>,
+    pub zoom: Option<i32>,
+    pub pan_range: Option<CtrlRange>,
+    pub tilt_range: Option<CtrlRange>,
+    pub zoom_range: Option<CtrlRange>,
+    pub focus_range: Option<CtrlRange>,
+}
+
 impl From<V4l2CtrlRange> for CtrlRange {
     fn from(r: V4l2CtrlRange) -> Self {
         CtrlRange {
@@ -242,18 +832,120 @@ impl From<V4l2CtrlRange> for CtrlRange {
     }
 }
 
+impl From<CtrlRange> for V4l2CtrlRange {
+    fn from(r: CtrlRange) -> Self {
+        V4l2CtrlRange {
+            minimum: r.minimum,
+            maximum: r.maximum,
+            step: r.step,
+            default_value: r.default_value,
+        }
+    }
+}
+
 impl Camera {
+    /// Open a camera by path or by part of its name or bus info, with the
+    /// soft limits saved for it. If those can't be loaded, it warns and
+    /// opens the camera without limits.
     pub fn new(hint: &str) -> Result<Self, Error> {
+        let (handle, device) = usbio::open_camera(hint)?;
+        #[cfg(feature = "profile")]
+        let limits = match &device {
+            Some(device) => LimitStore::load(LimitStore::default_path())
+                .map(|store| store.get(device))
+                .and_then(|limits| limits.validate().map(|()| limits))
+                .unwrap_or_else(|e| {
+                    eprintln!("Warning: soft limits not loaded: {}", e);
+                    SoftLimits::default()
+                }),
+            None => SoftLimits::default(),
+        };
+        #[cfg(not(feature = "profile"))]
+        let limits = SoftLimits::default();
         Ok(Self {
-            handle: usbio::open_camera(hint)?,
+            handle: Arc::new(handle.into()),
             verbose: false,
+            device,
+            clamp: false,
+            unverified: false,
+            limits: Mutex::new(limits),
+            fov_mode: Mutex::new(None),
+            exposure_mode: Mutex::new(None),
+            ranges: Mutex::new(BTreeMap::new()),
+            jog: Mutex::new(None),
         })
     }
 
+    /// The device this camera was opened from. `None` for simulated and
+    /// replayed cameras.
+    pub fn device(&self) -> Option<&DeviceInfo> {
+        self.device.as_ref()
+    }
+
+    /// List the video devices that could be opened as cameras.
+    pub fn list() -> Vec<DeviceInfo> {
+        usbio::list_devices()
+    }
+
     pub fn set_verbose(&mut self, verbose: bool) {
         self.verbose = verbose;
     }
 
+    /// Clamp out-of-range pan, tilt, zoom and focus values to the nearest
+    /// limit. By default they fail with [`Error::OutOfRange`].
+    pub fn set_clamp(&mut self, clamp: bool) {
+        self.clamp = clamp;
+    }
+
+    /// Send extension-unit commands whose bytes haven't been confirmed on a
+    /// device (see [`settings::XU_COMMANDS`]). By default they fail with
+    /// [`Error::Unverified`] before anything is sent.
+    pub fn set_unverified(&mut self, unverified: bool) {
+        self.unverified = unverified;
+    }
+
+    /// Record all traffic with the device to `trace`.
+    #[cfg(feature = "trace")]
+    pub fn with_trace(mut self, trace: Trace) -> Self {
+        let handle = Arc::into_inner(self.handle).expect("no movement while setting up");
+        self.handle = Arc::new(trace::TraceRecorder::new(handle, trace).into());
+        self
+    }
+
+    /// A camera backed by a [`sim::SimulatedCamera`] instead of a device.
+    pub fn simulated() -> Self {
+        Self {
+            handle: Arc::new(sim::SimulatedCamera::new().into()),
+            verbose: false,
+            device: None,
+            clamp: false,
+            unverified: false,
+            limits: Mutex::new(SoftLimits::default()),
+            fov_mode: Mutex::new(None),
+            exposure_mode: Mutex::new(None),
+            ranges: Mutex::new(BTreeMap::new()),
+            jog: Mutex::new(None),
+        }
+    }
+
+    /// A camera that plays back a recorded trace instead of talking to a
+    /// device. See [`TraceReplay`] for how mismatches are reported.
+    #[cfg(feature = "trace")]
+    pub fn replay(replay: TraceReplay) -> Self {
+        Self {
+            handle: Arc::new(replay.into()),
+            verbose: false,
+            device: None,
+            clamp: false,
+            unverified: false,
+            limits: Mutex::new(SoftLimits::default()),
+            fov_mode: Mutex::new(None),
+            exposure_mode: Mutex::new(None),
+            ranges: Mutex::new(BTreeMap::new()),
+            jog: Mutex::new(None),
+        }
+    }
+
     /// Try to open the camera, retrying every `interval` until it appears.
     /// Prints a message to stderr on the first failure, then silently retries.
     pub fn wait_for(hint: &str, interval: Duration) -> Self {
@@ -266,10 +958,14 @@ impl Camera {
                     }
                     return cam;
                 }
-                Err(_) if !warned => {
+                Err(Error::NoCameraFound) if !warned => {
                     eprintln!("Camera \"{}\" not found, waiting for it to appear...", hint);
                     warned = true;
                 }
+                Err(e) if !warned => {
+                    eprintln!("Camera \"{}\" can't be opened ({}), retrying...", hint, e);
+                    warned = true;
+                }
                 Err(_) => {}
             }
             thread::sleep(interval);
@@ -280,13 +976,6 @@ impl Camera {
         self.handle.info()
     }
 
-    pub fn get_status(&self) -> Result<CameraStatus, Error> {
-        let mut data: [u8; 60] = [0u8; 60];
-        self.get_cur(0x2, 0x6, &mut data)
-            .map_err(|x| Error::USBIOError(x.0))?;
-        Ok(CameraStatus::decode(&data))
-    }
-
     pub fn dump(&self) -> Result<(), Errno> {
         let mut data: [u8; 60] = [0u8; 60];
         self.get_cur(0x2, 0x6, &mut data)?;
@@ -301,100 +990,65 @@ impl Camera {
         Ok(())
     }
 
-    // ---- Standard V4L2 Pan/Tilt/Zoom controls ----
-
-    /// Get the current absolute pan value (in arc-seconds).
-    pub fn get_pan(&self) -> Result<i32, Error> {
-        self.handle
-            .get_ctrl(usbio::V4L2_CID_PAN_ABSOLUTE)
-            .map_err(|e| Error::USBIOError(e.0))
-    }
-
-    /// Set the absolute pan value (in arc-seconds).
-    pub fn set_pan(&self, value: i32) -> Result<(), Error> {
-        self.handle
-            .set_ctrl(usbio::V4L2_CID_PAN_ABSOLUTE, value)
-            .map_err(|e| Error::USBIOError(e.0))
-    }
-
-    /// Get the current absolute tilt value (in arc-seconds).
-    pub fn get_tilt(&self) -> Result<i32, Error> {
-        self.handle
-            .get_ctrl(usbio::V4L2_CID_TILT_ABSOLUTE)
-            .map_err(|e| Error::USBIOError(e.0))
-    }
-
-    /// Set the absolute tilt value (in arc-seconds).
-    pub fn set_tilt(&self, value: i32) -> Result<(), Error> {
-        self.handle
-            .set_ctrl(usbio::V4L2_CID_TILT_ABSOLUTE, value)
-            .map_err(|e| Error::USBIOError(e.0))
-    }
-
-    /// Get the current absolute zoom value.
-    pub fn get_zoom(&self) -> Result<i32, Error> {
-        self.handle
-            .get_ctrl(usbio::V4L2_CID_ZOOM_ABSOLUTE)
-            .map_err(|e| Error::USBIOError(e.0))
-    }
-
-    /// Set the absolute zoom value.
-    pub fn set_zoom(&self, value: i32) -> Result<(), Error> {
-        self.handle
-            .set_ctrl(usbio::V4L2_CID_ZOOM_ABSOLUTE, value)
-            .map_err(|e| Error::USBIOError(e.0))
-    }
-
-    /// Move pan by a relative amount.
-    pub fn pan_relative(&self, delta: i32) -> Result<(), Error> {
-        self.handle
-            .set_ctrl(usbio::V4L2_CID_PAN_RELATIVE, delta)
-            .map_err(|e| Error::USBIOError(e.0))
+    fn send(&self, request: Request) -> Result<(), Error> {
+        if let Some(command) = request.xu_command() {
+            if !command.is_verified() && !self.unverified {
+                return Err(Error::Unverified(command.name));
+            }
+        }
+        self.send_cmd(tiny2_proto::UNIT, request.selector, &request.data)
     }
 
-    /// Move tilt by a relative amount.
-    pub fn tilt_relative(&self, delta: i32) -> Result<(), Error> {
+    /// Fail with [`Error::Unsupported`] if the driver doesn't expose control `id`.
+    fn require_ctrl(&self, id: u32, name: &'static str) -> Result<(), Error> {
         self.handle
-            .set_ctrl(usbio::V4L2_CID_TILT_RELATIVE, delta)
-            .map_err(|e| Error::USBIOError(e.0))
+            .query_ctrl(id)
+            .map(|_| ())
+            .map_err(|_| Error::Unsupported(name))
     }
 
-    /// Move zoom by a relative amount.
-    pub fn zoom_relative(&self, delta: i32) -> Result<(), Error> {
-        self.handle
-            .set_ctrl(usbio::V4L2_CID_ZOOM_RELATIVE, delta)
-            .map_err(|e| Error::USBIOError(e.0))
+    /// The range of a V4L2 control, from the cache after the first query.
+    fn ctrl_range(&self, id: u32) -> Result<CtrlRange, Error> {
+        let mut ranges = self.ranges.lock().expect("range cache lock poisoned");
+        if let Some(range) = ranges.get(&id) {
+            return Ok(*range);
+        }
+        let range: CtrlRange = self
+            .handle
+            .query_ctrl(id)
+            .map_err(|e| Error::USBIOError(e.0))?
+            .into();
+        ranges.insert(id, range);
+        Ok(range)
     }
 
-    /// Query the supported range for pan (absolute).
-    pub fn query_pan_range(&self) -> Result<CtrlRange, Error> {
-        self.handle
-            .query_ctrl(usbio::V4L2_CID_PAN_ABSOLUTE)
-            .map(|r| r.into())
-            .map_err(|e| Error::USBIOError(e.0))
+    /// The soft limit on a control; unbounded for those without one.
+    fn limit(&self, id: u32) -> Limit {
+        let limits = self.limits.lock().expect("limits lock poisoned");
+        match id {
+            usbio::V4L2_CID_PAN_ABSOLUTE => limits.pan,
+            usbio::V4L2_CID_TILT_ABSOLUTE => limits.tilt,
+            usbio::V4L2_CID_ZOOM_ABSOLUTE => limits.zoom,
+            _ => Limit::default(),
+        }
     }
 
-    /// Query the supported range for tilt (absolute).
-    pub fn query_tilt_range(&self) -> Result<CtrlRange, Error> {
-        self.handle
-            .query_ctrl(usbio::V4L2_CID_TILT_ABSOLUTE)
-            .map(|r| r.into())
-            .map_err(|e| Error::USBIOError(e.0))
+    /// A control's range narrowed to its soft limit.
+    fn limited_range(&self, id: u32) -> Result<CtrlRange, Error> {
+        Ok(self.limit(id).restrict(&self.ctrl_range(id)?))
     }
 
-    /// Query the supported range for zoom (absolute).
-    pub fn query_zoom_range(&self) -> Result<CtrlRange, Error> {
+    /// Set a control after checking or clamping `value` against its range
+    /// and soft limit, and snapping it to a step.
+    fn set_ranged(&self, id: u32, control: &'static str, value: i32) -> Result<(), Error> {
+        let range = self.limited_range(id)?;
+        let value = if self.clamp {
+            range.clamp(value)
+        } else {
+            range.check(control, value)?
+        };
         self.handle
-            .query_ctrl(usbio::V4L2_CID_ZOOM_ABSOLUTE)
-            .map(|r| r.into())
-            .map_err(|e| Error::USBIOError(e.0))
-    }
-
-    pub fn send_cmd(&self, unit: u8, selector: u8, cmd: &[u8]) -> Result<(), Error> {
-        let mut data = [0u8; 60];
-        data[..cmd.len()].copy_from_slice(cmd);
-
-        self.set_cur(unit, selector, &mut data)
+            .set_ctrl(id, range.snap(value))
             .map_err(|e| Error::USBIOError(e.0))
     }
 
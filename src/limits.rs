@@ -0,0 +1,241 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! User-defined soft limits on pan, tilt and zoom.
+//!
+//! A [`Camera`](crate::Camera) narrows each control's range to its
+//! [`SoftLimits`] before checking or clamping a value, so absolute moves,
+//! relative moves, continuous moves and preset recalls all stay inside
+//! them. Limits are saved per camera in a [`LimitStore`], keyed by
+//! [`DeviceInfo::key`](crate::DeviceInfo::key), and loaded when the camera
+//! is opened.
+
+#[cfg(feature = "profile")]
+use crate::{profile::config_dir, DeviceInfo};
+use crate::{CtrlRange, Error};
+#[cfg(feature = "profile")]
+use std::{collections::BTreeMap, fs, io, path::Path, path::PathBuf};
+
+/// Bounds on one axis, in the control's own units. `None` leaves that end
+/// at the device's limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Limit {
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub min: Option<i32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub max: Option<i32>,
+}
+
+impl Limit {
+    pub fn is_unbounded(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    /// `range` narrowed to this limit. The new ends are moved inwards to
+    /// whole steps so that [`CtrlRange::snap`] stays within them; if no
+    /// step fits between them, the range is the single step nearest the
+    /// minimum.
+    pub fn restrict(&self, range: &CtrlRange) -> CtrlRange {
+        let step = range.step.max(1) as i64;
+        let steps = |v: i32| (v as i64 - range.minimum as i64) as f64 / step as f64;
+        let on_step = |n: f64| (range.minimum as i64 + n as i64 * step) as i32;
+        let top = on_step(steps(range.maximum).floor());
+        let minimum = match self.min {
+            Some(min) if min > range.minimum => on_step(steps(min).ceil()).min(top),
+            _ => range.minimum,
+        };
+        let maximum = match self.max {
+            Some(max) if max < range.maximum => on_step(steps(max).floor()).max(minimum),
+            _ => range.maximum,
+        };
+        CtrlRange {
+            minimum,
+            maximum,
+            step: range.step,
+            default_value: range.default_value.clamp(minimum, maximum),
+        }
+    }
+
+    fn validate(&self, axis: &'static str) -> Result<(), Error> {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) if min > max => Err(Error::InvalidLimit(axis, min, max)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Soft limits for each axis: pan and tilt in arc-seconds, zoom in zoom
+/// steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct SoftLimits {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Limit::is_unbounded"))]
+    pub pan: Limit,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Limit::is_unbounded"))]
+    pub tilt: Limit,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Limit::is_unbounded"))]
+    pub zoom: Limit,
+}
+
+impl SoftLimits {
+    /// Check that no axis has its minimum above its maximum.
+    pub fn validate(&self) -> Result<(), Error> {
+        self.pan.validate("pan")?;
+        self.tilt.validate("tilt")?;
+        self.zoom.validate("zoom")
+    }
+}
+
+/// Soft limits for every known camera, stored as TOML in the tiny2 config
+/// directory.
+#[cfg(feature = "profile")]
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LimitStore {
+    #[serde(default)]
+    pub cameras: BTreeMap<String, SoftLimits>,
+}
+
+#[cfg(feature = "profile")]
+impl LimitStore {
+    /// Load limits from a file. A missing file is an empty store.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        match fs::read_to_string(path) {
+            Ok(contents) => {
+                toml::from_str(&contents).map_err(|e| Error::ProfileFormat(e.to_string()))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let contents =
+            toml::to_string_pretty(self).map_err(|e| Error::ProfileFormat(e.to_string()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// `$XDG_CONFIG_HOME/tiny2/limits.toml`, falling back to `~/.config`.
+    pub fn default_path() -> PathBuf {
+        config_dir().join("limits.toml")
+    }
+
+    /// The limits saved for `device`, or none.
+    pub fn get(&self, device: &DeviceInfo) -> SoftLimits {
+        self.cameras.get(device.key()).copied().unwrap_or_default()
+    }
+
+    /// Save `limits` for `device`, dropping the entry if they are all
+    /// unbounded.
+    pub fn set(&mut self, device: &DeviceInfo, limits: SoftLimits) {
+        if limits == SoftLimits::default() {
+            self.cameras.remove(device.key());
+        } else {
+            self.cameras.insert(device.key().to_string(), limits);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// -10..=10 in steps of 3, so the whole steps are -10, -7, ..., 5, 8.
+    const RANGE: CtrlRange = CtrlRange {
+        minimum: -10,
+        maximum: 10,
+        step: 3,
+        default_value: -1,
+    };
+
+    fn limit(min: Option<i32>, max: Option<i32>) -> Limit {
+        Limit { min, max }
+    }
+
+    #[test]
+    fn unbounded_leaves_range() {
+        assert_eq!(Limit::default().restrict(&RANGE), RANGE);
+        assert_eq!(limit(Some(-20), Some(20)).restrict(&RANGE), RANGE);
+    }
+
+    #[test]
+    fn restrict_moves_ends_inwards_to_steps() {
+        let range = limit(Some(-5), Some(6)).restrict(&RANGE);
+        assert_eq!((range.minimum, range.maximum), (-4, 5));
+        assert_eq!(range.step, 3);
+        assert_eq!(range.default_value, -1);
+
+        let range = limit(Some(2), None).restrict(&RANGE);
+        assert_eq!((range.minimum, range.maximum), (2, 10));
+        assert_eq!(range.default_value, 2);
+    }
+
+    #[test]
+    fn restrict_past_last_step() {
+        // Above the last whole step the minimum stays on it.
+        let range = limit(Some(9), None).restrict(&RANGE);
+        assert_eq!((range.minimum, range.maximum), (8, 10));
+        assert_eq!(range.snap(10), 8);
+        // Below the range the maximum can't pass the minimum.
+        let range = limit(None, Some(-20)).restrict(&RANGE);
+        assert_eq!((range.minimum, range.maximum), (-10, -10));
+    }
+
+    #[test]
+    fn restrict_inverted_limit() {
+        let range = limit(Some(5), Some(-5)).restrict(&RANGE);
+        assert_eq!((range.minimum, range.maximum), (5, 5));
+        assert_eq!(range.snap(-10), 5);
+        assert!(limit(Some(5), Some(-5)).validate("pan").is_err());
+    }
+
+    #[test]
+    fn snap_to_steps() {
+        assert_eq!(RANGE.snap(-9), -10);
+        assert_eq!(RANGE.snap(-8), -7);
+        assert_eq!(RANGE.snap(3), 2);
+        assert_eq!(RANGE.snap(4), 5);
+    }
+
+    #[test]
+    fn snap_at_ends() {
+        assert_eq!(RANGE.snap(-10), -10);
+        assert_eq!(RANGE.snap(i32::MIN), -10);
+        // The maximum isn't a whole step from the minimum.
+        assert_eq!(RANGE.snap(10), 8);
+        assert_eq!(RANGE.snap(i32::MAX), 8);
+    }
+
+    #[test]
+    fn snap_without_step() {
+        let range = CtrlRange { step: 0, ..RANGE };
+        assert_eq!(range.snap(3), 3);
+        assert_eq!(range.snap(10), 10);
+    }
+
+    #[test]
+    fn clamp_and_check() {
+        assert_eq!(RANGE.clamp(-11), -10);
+        assert_eq!(RANGE.clamp(3), 3);
+        assert_eq!(RANGE.clamp(11), 10);
+        assert_eq!(RANGE.check("pan", 10).unwrap(), 10);
+        assert!(matches!(
+            RANGE.check("pan", 11),
+            Err(Error::OutOfRange { value: 11, .. })
+        ));
+    }
+}
@@ -0,0 +1,452 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! Smooth pan/tilt/zoom motion.
+//!
+//! A [`Move`] takes the camera from where it is to a target position, over a
+//! fixed time or at a given speed, following an [`Easing`] curve. It steps
+//! the absolute controls on a timer. [`Move::run`] does that on the calling
+//! thread; [`Move::spawn`] does it on a new one and returns a
+//! [`MotionHandle`] that can cancel it.
+//!
+//! Continuous movement at a set speed, as for a joystick, is
+//! [`OBSBotWebCam::move_continuous`]. Devices without V4L2 speed controls get
+//! it from a [`Jog`], which steps the absolute controls the same way.
+
+use crate::usbio::{self, CameraHandleType, UvcUsbIo, V4l2CtrlRange};
+use crate::{CtrlRange, Error, Limit, OBSBotWebCam};
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How progress along a move is spread over its duration.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Easing {
+    Linear,
+    /// Start slowly, end at full speed.
+    EaseIn,
+    /// Start at full speed, slow down at the end.
+    EaseOut,
+    /// Speed up, then slow down.
+    #[default]
+    EaseInOut,
+}
+
+impl Easing {
+    pub const ALL: [Easing; 4] = [
+        Easing::Linear,
+        Easing::EaseIn,
+        Easing::EaseOut,
+        Easing::EaseInOut,
+    ];
+
+    /// The fraction of the distance covered at fraction `t` of the time.
+    /// Both run from 0 to 1; the curves are cubic.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut if t < 0.5 => 4.0 * t * t * t,
+            Easing::EaseInOut => 1.0 - (2.0 - 2.0 * t).powi(3) / 2.0,
+        }
+    }
+}
+
+impl Display for Easing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Easing::Linear => write!(f, "Linear"),
+            Easing::EaseIn => write!(f, "Ease In"),
+            Easing::EaseOut => write!(f, "Ease Out"),
+            Easing::EaseInOut => write!(f, "Ease In Out"),
+        }
+    }
+}
+
+impl FromStr for Easing {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(tiny2_proto::parse_name(s, &Easing::ALL, "Easing")?)
+    }
+}
+
+/// A pan/tilt/zoom position. Axes that are `None` are left alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PtzPosition {
+    pub pan: Option<i32>,
+    pub tilt: Option<i32>,
+    pub zoom: Option<i32>,
+}
+
+impl PtzPosition {
+    /// Fail with [`Error::OutOfRange`] if an axis that is `Some` is outside
+    /// its control's range narrowed to the camera's soft limits.
+    fn check(&self, camera: &(impl OBSBotWebCam + ?Sized)) -> Result<(), Error> {
+        let limits = camera.get_soft_limits()?;
+        if let Some(pan) = self.pan {
+            limits
+                .pan
+                .restrict(&camera.query_pan_range()?)
+                .check("pan", pan)?;
+        }
+        if let Some(tilt) = self.tilt {
+            limits
+                .tilt
+                .restrict(&camera.query_tilt_range()?)
+                .check("tilt", tilt)?;
+        }
+        if let Some(zoom) = self.zoom {
+            limits
+                .zoom
+                .restrict(&camera.query_zoom_range()?)
+                .check("zoom", zoom)?;
+        }
+        Ok(())
+    }
+
+    /// Read the axes that are `Some` in `axes` from the camera.
+    fn read(camera: &(impl OBSBotWebCam + ?Sized), axes: &PtzPosition) -> Result<Self, Error> {
+        Ok(PtzPosition {
+            pan: axes.pan.map(|_| camera.get_pan()).transpose()?,
+            tilt: axes.tilt.map(|_| camera.get_tilt()).transpose()?,
+            zoom: axes.zoom.map(|_| camera.get_zoom()).transpose()?,
+        })
+    }
+
+    /// Set the axes that are `Some` and differ from `last`.
+    fn apply(
+        &self,
+        camera: &(impl OBSBotWebCam + ?Sized),
+        last: &PtzPosition,
+    ) -> Result<(), Error> {
+        if let Some(pan) = self.pan.filter(|&v| Some(v) != last.pan) {
+            camera.set_pan(pan)?;
+        }
+        if let Some(tilt) = self.tilt.filter(|&v| Some(v) != last.tilt) {
+            camera.set_tilt(tilt)?;
+        }
+        if let Some(zoom) = self.zoom.filter(|&v| Some(v) != last.zoom) {
+            camera.set_zoom(zoom)?;
+        }
+        Ok(())
+    }
+}
+
+/// How long a move takes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Timing {
+    Duration(Duration),
+    /// Average speeds: arc-seconds per second for pan and tilt, zoom units
+    /// per second for zoom. The slowest axis sets the duration, and the
+    /// others are slowed to arrive with it. An axis with a speed of zero
+    /// just keeps pace.
+    Speed {
+        pan_tilt: f64,
+        zoom: f64,
+    },
+}
+
+/// A move worked out from its starting position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plan {
+    pub from: PtzPosition,
+    pub to: PtzPosition,
+    pub duration: Duration,
+    pub easing: Easing,
+}
+
+impl Plan {
+    /// Where the camera should be `elapsed` into the move.
+    pub fn at(&self, elapsed: Duration) -> PtzPosition {
+        let t = if self.duration.is_zero() {
+            1.0
+        } else {
+            elapsed.as_secs_f64() / self.duration.as_secs_f64()
+        };
+        let progress = self.easing.apply(t);
+        let axis = |from: Option<i32>, to: Option<i32>| {
+            let to = to? as i64;
+            let from = from.map_or(to, i64::from);
+            // Between `from` and `to`, so it fits back in an i32.
+            Some((from + ((to - from) as f64 * progress).round() as i64) as i32)
+        };
+        PtzPosition {
+            pan: axis(self.from.pan, self.to.pan),
+            tilt: axis(self.from.tilt, self.to.tilt),
+            zoom: axis(self.from.zoom, self.to.zoom),
+        }
+    }
+}
+
+/// A smooth move to a target position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Move {
+    pub target: PtzPosition,
+    pub timing: Timing,
+    pub easing: Easing,
+    /// Time between steps.
+    pub interval: Duration,
+}
+
+impl Move {
+    /// A move to `target` taking one second, easing in and out.
+    pub fn to(target: PtzPosition) -> Self {
+        Move {
+            target,
+            timing: Timing::Duration(Duration::from_secs(1)),
+            easing: Easing::default(),
+            interval: Duration::from_millis(50),
+        }
+    }
+
+    pub fn over(mut self, duration: Duration) -> Self {
+        self.timing = Timing::Duration(duration);
+        self
+    }
+
+    /// See [`Timing::Speed`].
+    pub fn at_speed(mut self, pan_tilt: f64, zoom: f64) -> Self {
+        self.timing = Timing::Speed { pan_tilt, zoom };
+        self
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Work out the move from `from`. Fails if the speeds are so low that
+    /// the move would take longer than a [`Duration`] can hold.
+    pub fn plan(&self, from: PtzPosition) -> Result<Plan, Error> {
+        let duration = match self.timing {
+            Timing::Duration(duration) => duration,
+            Timing::Speed { pan_tilt, zoom } => {
+                let distance = |from: Option<i32>, to: Option<i32>| match (from, to) {
+                    (Some(from), Some(to)) => (to as i64 - from as i64).unsigned_abs() as f64,
+                    _ => 0.0,
+                };
+                let time = |distance: f64, speed: f64| {
+                    if distance == 0.0 || speed <= 0.0 {
+                        0.0
+                    } else {
+                        distance / speed
+                    }
+                };
+                let secs = [
+                    time(distance(from.pan, self.target.pan), pan_tilt),
+                    time(distance(from.tilt, self.target.tilt), pan_tilt),
+                    time(distance(from.zoom, self.target.zoom), zoom),
+                ]
+                .into_iter()
+                .fold(0.0, f64::max);
+                Duration::try_from_secs_f64(secs).map_err(|_| Error::InvalidDuration(secs))?
+            }
+        };
+        Ok(Plan {
+            from,
+            to: self.target,
+            duration,
+            easing: self.easing,
+        })
+    }
+
+    /// Make the move on this thread, checking `cancel` before each step.
+    /// Returns whether the target was reached; a cancelled move stops where
+    /// it is. A target outside the camera's ranges or soft limits fails
+    /// before anything moves.
+    pub fn run(
+        &self,
+        camera: &(impl OBSBotWebCam + ?Sized),
+        cancel: &AtomicBool,
+    ) -> Result<bool, Error> {
+        self.target.check(camera)?;
+        let from = PtzPosition::read(camera, &self.target)?;
+        let plan = self.plan(from)?;
+        let start = Instant::now();
+        let mut last = from;
+        loop {
+            if cancel.load(Ordering::Relaxed) {
+                return Ok(false);
+            }
+            let elapsed = start.elapsed();
+            let position = plan.at(elapsed);
+            position.apply(camera, &last)?;
+            last = position;
+            if elapsed >= plan.duration {
+                return Ok(true);
+            }
+            thread::sleep(self.interval.min(plan.duration - elapsed));
+        }
+    }
+
+    /// Make the move on a new thread.
+    pub fn spawn(self, camera: Arc<dyn OBSBotWebCam + Send + Sync>) -> MotionHandle {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = cancel.clone();
+        let thread = thread::spawn(move || self.run(&*camera, &flag));
+        MotionHandle {
+            cancel,
+            thread: Some(thread),
+        }
+    }
+}
+
+/// A move running on its own thread. Dropping the handle cancels the move.
+#[derive(Debug)]
+#[must_use = "dropping a MotionHandle cancels the move"]
+pub struct MotionHandle {
+    cancel: Arc<AtomicBool>,
+    thread: Option<JoinHandle<Result<bool, Error>>>,
+}
+
+impl MotionHandle {
+    /// Stop the move at its next step.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(|t| t.is_finished())
+    }
+
+    /// Wait for the move to end. See [`Move::run`] for the result.
+    pub fn join(mut self) -> Result<bool, Error> {
+        match self.thread.take() {
+            Some(thread) => thread.join().expect("motion thread panicked"),
+            None => Ok(false),
+        }
+    }
+}
+
+impl Drop for MotionHandle {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// The absolute controls a [`Jog`] steps, in `move_continuous` order.
+const JOG_CONTROLS: [u32; 3] = [
+    usbio::V4L2_CID_PAN_ABSOLUTE,
+    usbio::V4L2_CID_TILT_ABSOLUTE,
+    usbio::V4L2_CID_ZOOM_ABSOLUTE,
+];
+
+/// How long a [`Jog`] at full speed takes to cross an axis's whole range.
+const JOG_FULL_RANGE: Duration = Duration::from_secs(5);
+
+const JOG_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The value of a V4L2 speed control for `speed`, a fraction of full speed
+/// from -1 to 1. Any nonzero speed moves at least one unit.
+pub(crate) fn native_speed(speed: f64, range: V4l2CtrlRange) -> i32 {
+    let limit = if speed >= 0.0 {
+        range.maximum
+    } else {
+        range.minimum
+    };
+    let value = (speed.abs() * limit as f64).round() as i32;
+    if value == 0 && speed != 0.0 {
+        limit.signum()
+    } else {
+        value
+    }
+}
+
+/// Continuous pan, tilt and zoom for devices without speed controls: a
+/// thread that steps the absolute controls on a timer. Speeds are fractions
+/// of full speed, as for `move_continuous`, and can be changed while it
+/// runs. It ends when they are all zero, at the end of a range, or on the
+/// first error.
+#[derive(Debug)]
+pub(crate) struct Jog {
+    speeds: Arc<Mutex<[f64; 3]>>,
+    thread: JoinHandle<Result<(), Error>>,
+}
+
+impl Jog {
+    /// Start moving. `ranges` are those of the absolute controls, which set
+    /// full speed, and the soft limits to stay within. Axes without a range
+    /// don't move.
+    pub(crate) fn start(
+        handle: Arc<CameraHandleType>,
+        ranges: [Option<(CtrlRange, Limit)>; 3],
+        speeds: [f64; 3],
+    ) -> Result<Self, Error> {
+        // Track every axis the device has, so that later speed changes can
+        // start any of them, but only fail for the ones asked to move.
+        let mut axes = Vec::new();
+        for (i, id) in JOG_CONTROLS.into_iter().enumerate() {
+            let Some((range, limit)) = ranges[i] else {
+                continue;
+            };
+            let span = (range.maximum - range.minimum) as f64;
+            match handle.get_ctrl(id) {
+                Ok(position) => axes.push((i, id, span, limit.restrict(&range), position as f64)),
+                Err(e) if speeds[i] != 0.0 => return Err(Error::USBIOError(e.0)),
+                Err(_) => {}
+            }
+        }
+        let speeds = Arc::new(Mutex::new(speeds));
+        let shared = speeds.clone();
+        let thread = thread::spawn(move || Self::run(&handle, &shared, axes));
+        Ok(Jog { speeds, thread })
+    }
+
+    fn run(
+        handle: &CameraHandleType,
+        speeds: &Mutex<[f64; 3]>,
+        mut axes: Vec<(usize, u32, f64, CtrlRange, f64)>,
+    ) -> Result<(), Error> {
+        let mut last = Instant::now();
+        loop {
+            thread::sleep(JOG_INTERVAL);
+            let speeds = *speeds.lock().expect("jog speeds lock poisoned");
+            if speeds.iter().all(|&s| s == 0.0) {
+                return Ok(());
+            }
+            let dt = last.elapsed().as_secs_f64() / JOG_FULL_RANGE.as_secs_f64();
+            last = Instant::now();
+            let mut moving = false;
+            for (i, id, span, range, position) in &mut axes {
+                let (min, max) = (range.minimum as f64, range.maximum as f64);
+                let next = (*position + speeds[*i] * *span * dt).clamp(min, max);
+                let snapped = |v: f64| range.snap(v.round() as i32);
+                if snapped(next) != snapped(*position) {
+                    handle
+                        .set_ctrl(*id, snapped(next))
+                        .map_err(|e| Error::USBIOError(e.0))?;
+                }
+                moving |= next != *position;
+                *position = next;
+            }
+            if !moving {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Change the speeds.
+    pub(crate) fn set_speeds(&self, speeds: [f64; 3]) {
+        *self.speeds.lock().expect("jog speeds lock poisoned") = speeds;
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Stop moving and wait for the thread to end.
+    pub(crate) fn stop(self) -> Result<(), Error> {
+        self.set_speeds([0.0; 3]);
+        self.thread.join().expect("jog thread panicked")
+    }
+}
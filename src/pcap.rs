@@ -0,0 +1,314 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! Import of USB captures made with USBPcap (e.g. in Wireshark on Windows),
+//! to see what OBSBOT Center sends to the camera.
+//!
+//! Both pcap and pcapng files are read. Only the USBPcap link type is
+//! understood; UVC class control requests are paired with their completions
+//! and decoded with the commands this crate knows.
+
+use crate::Error;
+use std::collections::HashMap;
+use std::path::Path;
+
+pub use tiny2_proto::command::describe;
+
+/// LINKTYPE_USBPCAP
+const LINKTYPE_USBPCAP: u32 = 249;
+
+/// A captured packet.
+#[derive(Debug, Clone)]
+pub struct Packet {
+    /// Capture time in seconds.
+    pub time: f64,
+    pub linktype: u32,
+    pub data: Vec<u8>,
+}
+
+/// A UVC class-specific control request, e.g. SET_CUR to an extension unit.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ControlRequest {
+    /// Time the request was sent, in seconds.
+    pub time: f64,
+    /// bRequest: 0x01 SET_CUR, 0x81 GET_CUR, 0x85 GET_LEN, ...
+    pub request: u8,
+    pub unit: u8,
+    pub selector: u8,
+    /// Data sent (SET) or received (GET).
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex_bytes"))]
+    pub data: Vec<u8>,
+    /// USBD status of the completion, if it was captured.
+    pub status: Option<u32>,
+}
+
+impl ControlRequest {
+    pub fn request_name(&self) -> String {
+        match self.request {
+            0x01 => "SET_CUR".to_string(),
+            0x81 => "GET_CUR".to_string(),
+            0x82 => "GET_MIN".to_string(),
+            0x83 => "GET_MAX".to_string(),
+            0x84 => "GET_RES".to_string(),
+            0x85 => "GET_LEN".to_string(),
+            0x86 => "GET_INFO".to_string(),
+            0x87 => "GET_DEF".to_string(),
+            r => format!("0x{:02x}", r),
+        }
+    }
+
+    /// The data with the zero padding the host adds to fixed-size buffers
+    /// removed. Selector 0x06 commands keep their `[cmd, len, value...]`.
+    pub fn trimmed_data(&self) -> &[u8] {
+        let mut end = self.data.len();
+        while end > 0 && self.data[end - 1] == 0 {
+            end -= 1;
+        }
+        if self.selector == 0x06 && self.data.len() >= 2 {
+            end = end.max((2 + self.data[1] as usize).min(self.data.len()));
+        }
+        &self.data[..end]
+    }
+
+    /// What the request does, if it is one this crate knows.
+    pub fn describe(&self) -> Option<String> {
+        describe(self.request, self.selector, &self.data)
+    }
+}
+
+fn u16_at(data: &[u8], offset: usize, big_endian: bool) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?.try_into().ok()?;
+    Some(if big_endian {
+        u16::from_be_bytes(bytes)
+    } else {
+        u16::from_le_bytes(bytes)
+    })
+}
+
+fn u32_at(data: &[u8], offset: usize, big_endian: bool) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?.try_into().ok()?;
+    Some(if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    })
+}
+
+fn truncated() -> Error {
+    Error::CaptureFormat("file is truncated".to_string())
+}
+
+/// Read all packets from a pcap or pcapng file.
+pub fn read_packets(path: impl AsRef<Path>) -> Result<Vec<Packet>, Error> {
+    let bytes = std::fs::read(path)?;
+    match u32_at(&bytes, 0, false).ok_or_else(truncated)? {
+        0x0a0d0d0a => read_pcapng(&bytes),
+        0xa1b2c3d4 | 0xd4c3b2a1 | 0xa1b23c4d | 0x4d3cb2a1 => read_pcap(&bytes),
+        magic => Err(Error::CaptureFormat(format!(
+            "not a pcap or pcapng file (magic 0x{:08x})",
+            magic
+        ))),
+    }
+}
+
+fn read_pcap(bytes: &[u8]) -> Result<Vec<Packet>, Error> {
+    let magic = u32_at(bytes, 0, false).ok_or_else(truncated)?;
+    let big_endian = matches!(magic, 0xd4c3b2a1 | 0x4d3cb2a1);
+    let subsecond = if matches!(magic, 0xa1b23c4d | 0x4d3cb2a1) {
+        1e-9
+    } else {
+        1e-6
+    };
+    let linktype = u32_at(bytes, 20, big_endian).ok_or_else(truncated)?;
+
+    let mut packets = Vec::new();
+    let mut offset = 24;
+    while offset < bytes.len() {
+        let seconds = u32_at(bytes, offset, big_endian).ok_or_else(truncated)?;
+        let fraction = u32_at(bytes, offset + 4, big_endian).ok_or_else(truncated)?;
+        let len = u32_at(bytes, offset + 8, big_endian).ok_or_else(truncated)? as usize;
+        let data = bytes
+            .get(offset + 16..offset + 16 + len)
+            .ok_or_else(truncated)?;
+        packets.push(Packet {
+            time: seconds as f64 + fraction as f64 * subsecond,
+            linktype,
+            data: data.to_vec(),
+        });
+        offset += 16 + len;
+    }
+    Ok(packets)
+}
+
+fn read_pcapng(bytes: &[u8]) -> Result<Vec<Packet>, Error> {
+    let mut packets = Vec::new();
+    let mut big_endian = false;
+    // Link type and timestamp resolution of each interface in the section.
+    let mut interfaces: Vec<(u32, f64)> = Vec::new();
+
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let block_type = u32_at(bytes, offset, big_endian).ok_or_else(truncated)?;
+        if block_type == 0x0a0d0d0a {
+            // Section header: the byte-order magic decides the endianness.
+            big_endian = match u32_at(bytes, offset + 8, false).ok_or_else(truncated)? {
+                0x1a2b3c4d => false,
+                0x4d3c2b1a => true,
+                _ => return Err(Error::CaptureFormat("bad byte-order magic".to_string())),
+            };
+            interfaces.clear();
+        }
+        let len = u32_at(bytes, offset + 4, big_endian).ok_or_else(truncated)? as usize;
+        if len < 12 {
+            return Err(Error::CaptureFormat(format!("bad block length {}", len)));
+        }
+        let body = bytes
+            .get(offset + 8..offset + len - 4)
+            .ok_or_else(truncated)?;
+
+        match block_type {
+            // Interface description
+            1 => {
+                let linktype = u16_at(body, 0, big_endian).ok_or_else(truncated)? as u32;
+                interfaces.push((
+                    linktype,
+                    ts_resolution(body.get(8..).unwrap_or(&[]), big_endian),
+                ));
+            }
+            // Enhanced packet
+            6 => {
+                let interface = u32_at(body, 0, big_endian).ok_or_else(truncated)? as usize;
+                let high = u32_at(body, 4, big_endian).ok_or_else(truncated)? as u64;
+                let low = u32_at(body, 8, big_endian).ok_or_else(truncated)? as u64;
+                let caplen = u32_at(body, 12, big_endian).ok_or_else(truncated)? as usize;
+                let data = body.get(20..20 + caplen).ok_or_else(truncated)?;
+                let &(linktype, resolution) = interfaces.get(interface).ok_or_else(|| {
+                    Error::CaptureFormat(format!("packet on unknown interface {}", interface))
+                })?;
+                packets.push(Packet {
+                    time: ((high << 32) | low) as f64 * resolution,
+                    linktype,
+                    data: data.to_vec(),
+                });
+            }
+            // Simple packet: interface 0, no timestamp
+            3 => {
+                let &(linktype, _) = interfaces.first().ok_or_else(|| {
+                    Error::CaptureFormat("packet before interface description".to_string())
+                })?;
+                let len = u32_at(body, 0, big_endian).ok_or_else(truncated)? as usize;
+                // The packet is cut to the snap length without saying so.
+                let data = body.get(4..).ok_or_else(truncated)?;
+                let data = &data[..len.min(data.len())];
+                packets.push(Packet {
+                    time: 0.0,
+                    linktype,
+                    data: data.to_vec(),
+                });
+            }
+            _ => {}
+        }
+        offset += len;
+    }
+    Ok(packets)
+}
+
+/// The if_tsresol option of an interface description, in seconds per tick.
+fn ts_resolution(mut options: &[u8], big_endian: bool) -> f64 {
+    while let (Some(code), Some(len)) = (
+        u16_at(options, 0, big_endian),
+        u16_at(options, 2, big_endian),
+    ) {
+        let len = len as usize;
+        if code == 0 {
+            break;
+        }
+        if let (9, 1, Some(&v)) = (code, len, options.get(4)) {
+            return if v & 0x80 == 0 {
+                10f64.powi(-((v & 0x7f) as i32))
+            } else {
+                2f64.powi(-((v & 0x7f) as i32))
+            };
+        }
+        options = options.get(4 + len.next_multiple_of(4)..).unwrap_or(&[]);
+    }
+    1e-6
+}
+
+/// Pull the UVC class control requests out of USBPcap packets, pairing each
+/// request with its completion.
+pub fn control_requests(packets: &[Packet]) -> Result<Vec<ControlRequest>, Error> {
+    let mut requests = Vec::new();
+    // Requests waiting for their completion, by IRP id.
+    let mut pending: HashMap<u64, ControlRequest> = HashMap::new();
+
+    for packet in packets {
+        if packet.linktype != LINKTYPE_USBPCAP {
+            return Err(Error::CaptureFormat(format!(
+                "link type {} is not USBPcap",
+                packet.linktype
+            )));
+        }
+        let data = &packet.data;
+        let (Some(header_len), Some(irp), Some(status), Some(&info), Some(&transfer)) = (
+            u16_at(data, 0, false),
+            data.get(2..10),
+            u32_at(data, 10, false),
+            data.get(16),
+            data.get(22),
+        ) else {
+            continue;
+        };
+        // Only control transfers carry a stage byte.
+        let Some(&stage) = data.get(27).filter(|_| transfer == 2) else {
+            continue;
+        };
+        let irp = u64::from_le_bytes(irp.try_into().unwrap());
+        let payload = data.get(header_len as usize..).unwrap_or(&[]);
+        let from_device = info & 1 != 0;
+
+        match stage {
+            // Setup: the 8-byte setup packet, followed by any OUT data.
+            0 if !from_device && payload.len() >= 8 => {
+                let request_type = payload[0];
+                // Class request to an interface (the UVC units live there).
+                if request_type & 0x7f != 0x21 {
+                    continue;
+                }
+                pending.insert(
+                    irp,
+                    ControlRequest {
+                        time: packet.time,
+                        request: payload[1],
+                        selector: payload[3],
+                        unit: payload[5],
+                        data: payload[8..].to_vec(),
+                        status: None,
+                    },
+                );
+            }
+            // Data stage of an OUT transfer.
+            1 if !from_device => {
+                if let Some(request) = pending.get_mut(&irp) {
+                    request.data.extend_from_slice(payload);
+                }
+            }
+            // Completion, with the IN data.
+            3 if from_device => {
+                if let Some(mut request) = pending.remove(&irp) {
+                    if request.request & 0x80 != 0 {
+                        request.data = payload.to_vec();
+                    }
+                    request.status = Some(status);
+                    requests.push(request);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Requests whose completion wasn't captured.
+    requests.extend(pending.into_values());
+    requests.sort_by(|a, b| a.time.total_cmp(&b.time));
+    Ok(requests)
+}
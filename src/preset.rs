@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! Named pan/tilt/zoom positions, stored as TOML in the tiny2 config directory.
+
+use crate::{motion::PtzPosition, profile::config_dir, Error, OBSBotWebCam};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, path::Path, path::PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Preset {
+    pub pan: i32,
+    pub tilt: i32,
+    pub zoom: i32,
+}
+
+impl Preset {
+    /// Capture the camera's current position.
+    pub fn capture(camera: &(impl OBSBotWebCam + ?Sized)) -> Result<Self, Error> {
+        Ok(Preset {
+            pan: camera.get_pan()?,
+            tilt: camera.get_tilt()?,
+            zoom: camera.get_zoom()?,
+        })
+    }
+
+    /// Move the camera to this position.
+    pub fn recall(&self, camera: &(impl OBSBotWebCam + ?Sized)) -> Result<(), Error> {
+        camera.set_pan(self.pan)?;
+        camera.set_tilt(self.tilt)?;
+        camera.set_zoom(self.zoom)
+    }
+}
+
+impl From<Preset> for PtzPosition {
+    fn from(p: Preset) -> Self {
+        PtzPosition {
+            pan: Some(p.pan),
+            tilt: Some(p.tilt),
+            zoom: Some(p.zoom),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PresetStore {
+    #[serde(default)]
+    pub presets: BTreeMap<String, Preset>,
+}
+
+impl PresetStore {
+    /// Load presets from a file. A missing file is an empty store.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        match fs::read_to_string(path) {
+            Ok(contents) => {
+                toml::from_str(&contents).map_err(|e| Error::ProfileFormat(e.to_string()))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let contents =
+            toml::to_string_pretty(self).map_err(|e| Error::ProfileFormat(e.to_string()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// `$XDG_CONFIG_HOME/tiny2/presets.toml`, falling back to `~/.config`.
+    pub fn default_path() -> PathBuf {
+        config_dir().join("presets.toml")
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Preset, Error> {
+        self.presets
+            .get(name)
+            .ok_or_else(|| Error::NoSuchPreset(name.to_string()))
+    }
+}
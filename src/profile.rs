@@ -0,0 +1,220 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! Saved camera settings that can be captured from a camera, stored as TOML
+//! or JSON, and applied back.
+
+use crate::{AIMode, Error, ExposureMode, FOVMode, FocusMode, OBSBotWebCam, PowerLineFrequency};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, path::PathBuf};
+
+/// A set of camera settings. Fields that are `None` are left untouched when
+/// the profile is applied.
+///
+/// Tracking mode, auto zoom and target lock use unconfirmed command bytes,
+/// so they aren't part of a profile: tiny2d applies profiles on hotplug.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ai_mode: Option<AIMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hdr: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fov: Option<FOVMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exposure_mode: Option<ExposureMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pan: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tilt: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zoom: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub power_line_frequency: Option<PowerLineFrequency>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focus_mode: Option<FocusMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focus: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mirror: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flip: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brightness: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contrast: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saturation: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sharpness: Option<i32>,
+}
+
+/// Which fields of a profile were applied, and which failed.
+#[derive(Debug, Default)]
+pub struct ApplyReport {
+    pub applied: Vec<&'static str>,
+    pub failed: Vec<(&'static str, Error)>,
+}
+
+impl ApplyReport {
+    pub fn is_ok(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    fn record(&mut self, field: &'static str, result: Result<(), Error>) {
+        match result {
+            Ok(()) => self.applied.push(field),
+            Err(e) => self.failed.push((field, e)),
+        }
+    }
+}
+
+impl Profile {
+    /// Capture the current settings of a camera. FOV and exposure mode can't
+    /// be read back from the device, so they are the ones last set through
+    /// `camera`, and are left unset if there aren't any.
+    pub fn capture(camera: &(impl OBSBotWebCam + ?Sized)) -> Result<Self, Error> {
+        let state = camera.get_state()?;
+        Ok(Profile {
+            ai_mode: state.status.ai_mode,
+            hdr: Some(state.status.hdr_on),
+            fov: camera.get_fov_mode()?,
+            exposure_mode: camera.get_exposure_mode()?,
+            pan: state.pan,
+            tilt: state.tilt,
+            zoom: state.zoom,
+            power_line_frequency: state.power_line_frequency,
+            focus: state
+                .focus
+                .filter(|_| state.focus_mode == Some(FocusMode::Manual)),
+            focus_mode: state.focus_mode,
+            mirror: state.mirror,
+            flip: state.flip,
+            brightness: camera.get_brightness().ok(),
+            contrast: camera.get_contrast().ok(),
+            saturation: camera.get_saturation().ok(),
+            sharpness: camera.get_sharpness().ok(),
+        })
+    }
+
+    /// Apply every set field to the camera, continuing past failures.
+    pub fn apply(&self, camera: &(impl OBSBotWebCam + ?Sized)) -> ApplyReport {
+        let mut report = ApplyReport::default();
+
+        // AI mode goes first, since tracking takes over PTZ.
+        if let Some(mode) = self.ai_mode {
+            report.record("ai_mode", camera.set_ai_mode(mode));
+        }
+        if let Some(on) = self.hdr {
+            report.record("hdr", camera.set_hdr_mode(on));
+        }
+        if let Some(mode) = self.fov {
+            report.record("fov", camera.set_fov(mode));
+        }
+        if let Some(mode) = self.exposure_mode {
+            report.record("exposure_mode", camera.set_exposure_mode(mode));
+        }
+        if let Some(freq) = self.power_line_frequency {
+            report.record(
+                "power_line_frequency",
+                camera.set_power_line_frequency(freq),
+            );
+        }
+        if let Some(on) = self.mirror {
+            report.record("mirror", camera.set_mirror(on));
+        }
+        if let Some(on) = self.flip {
+            report.record("flip", camera.set_flip(on));
+        }
+        if let Some(value) = self.brightness {
+            report.record("brightness", camera.set_brightness(value));
+        }
+        if let Some(value) = self.contrast {
+            report.record("contrast", camera.set_contrast(value));
+        }
+        if let Some(value) = self.saturation {
+            report.record("saturation", camera.set_saturation(value));
+        }
+        if let Some(value) = self.sharpness {
+            report.record("sharpness", camera.set_sharpness(value));
+        }
+        // Manual focus only sticks once the focus mode is manual.
+        if let Some(mode) = self.focus_mode {
+            report.record("focus_mode", camera.set_focus_mode(mode));
+        }
+        if let Some(value) = self.focus {
+            report.record("focus", camera.set_focus(value));
+        }
+        if let Some(value) = self.pan {
+            report.record("pan", camera.set_pan(value));
+        }
+        if let Some(value) = self.tilt {
+            report.record("tilt", camera.set_tilt(value));
+        }
+        if let Some(value) = self.zoom {
+            report.record("zoom", camera.set_zoom(value));
+        }
+
+        report
+    }
+
+    pub fn to_toml(&self) -> Result<String, Error> {
+        toml::to_string_pretty(self).map_err(|e| Error::ProfileFormat(e.to_string()))
+    }
+
+    pub fn from_toml(s: &str) -> Result<Self, Error> {
+        toml::from_str(s).map_err(|e| Error::ProfileFormat(e.to_string()))
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::ProfileFormat(e.to_string()))
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, Error> {
+        serde_json::from_str(s).map_err(|e| Error::ProfileFormat(e.to_string()))
+    }
+
+    /// Load a profile, as JSON if the file name ends in `.json` and TOML otherwise.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)?;
+        if is_json(path) {
+            Self::from_json(&contents)
+        } else {
+            Self::from_toml(&contents)
+        }
+    }
+
+    /// Save a profile, as JSON if the file name ends in `.json` and TOML otherwise.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let contents = if is_json(path) {
+            self.to_json()?
+        } else {
+            self.to_toml()?
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// `$XDG_CONFIG_HOME/tiny2/profile.toml`, falling back to `~/.config`.
+    pub fn default_path() -> PathBuf {
+        config_dir().join("profile.toml")
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+}
+
+/// The tiny2 configuration directory.
+pub fn config_dir() -> PathBuf {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_else(|| PathBuf::from("."));
+    base.join("tiny2")
+}
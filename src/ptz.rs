@@ -0,0 +1,121 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! Units for pan, tilt and zoom.
+//!
+//! The V4L2 controls take pan and tilt in arc-seconds and zoom in
+//! device-specific steps. [`Angle`] converts to and from degrees, and
+//! [`ZoomRatio`] maps zoom steps onto a magnification using the control's
+//! [`CtrlRange`].
+
+use crate::{CtrlRange, Error};
+use std::fmt::Display;
+use std::str::FromStr;
+
+pub const ARCSEC_PER_DEGREE: f64 = 3600.0;
+
+/// A pan or tilt angle, stored in arc-seconds as the device uses them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct Angle(i32);
+
+impl Angle {
+    pub const fn from_arcsec(arcsec: i32) -> Self {
+        Angle(arcsec)
+    }
+
+    /// Rounded to the nearest arc-second.
+    pub fn from_degrees(degrees: f64) -> Self {
+        Angle((degrees * ARCSEC_PER_DEGREE).round() as i32)
+    }
+
+    pub const fn arcsec(self) -> i32 {
+        self.0
+    }
+
+    pub fn degrees(self) -> f64 {
+        self.0 as f64 / ARCSEC_PER_DEGREE
+    }
+}
+
+impl Display for Angle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.2}°", self.degrees())
+    }
+}
+
+/// Degrees with a `deg` or `°` suffix, e.g. `-12.5deg`, or a whole number
+/// of arc-seconds.
+impl FromStr for Angle {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::UnsupportedName("angle".to_string(), s.to_string());
+        let trimmed = s.trim();
+        match trimmed
+            .strip_suffix("deg")
+            .or_else(|| trimmed.strip_suffix('°'))
+        {
+            Some(degrees) => degrees
+                .trim()
+                .parse()
+                .map(Angle::from_degrees)
+                .map_err(|_| invalid()),
+            None => trimmed.parse().map(Angle).map_err(|_| invalid()),
+        }
+    }
+}
+
+/// A zoom magnification, 1.0 being fully zoomed out. The device's zoom
+/// range maps linearly onto 1.0 to the model's
+/// [`max_zoom`](crate::FovCalibration::max_zoom).
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct ZoomRatio(pub f64);
+
+impl ZoomRatio {
+    /// The ratio for the zoom control value `value`, on a camera that
+    /// magnifies `max_zoom` times at the top of `range`.
+    pub fn from_ctrl(value: i32, range: &CtrlRange, max_zoom: f64) -> Self {
+        let span = (range.maximum as f64 - range.minimum as f64).max(1.0);
+        let t = (value as f64 - range.minimum as f64) / span;
+        ZoomRatio(1.0 + t * (max_zoom - 1.0))
+    }
+
+    /// The zoom control value for this ratio, before snapping. Ratios
+    /// outside 1.0 to `max_zoom` are clamped to it, and NaN counts as 1.0.
+    pub fn to_ctrl(self, range: &CtrlRange, max_zoom: f64) -> i32 {
+        let ratio = self.0.max(1.0).min(max_zoom);
+        let t = (ratio - 1.0) / (max_zoom - 1.0);
+        let span = range.maximum as f64 - range.minimum as f64;
+        (range.minimum as f64 + t * span).round() as i32
+    }
+}
+
+impl Display for ZoomRatio {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.1}x", self.0)
+    }
+}
+
+/// A number with an optional `x` suffix, e.g. `2x`.
+impl FromStr for ZoomRatio {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        trimmed
+            .strip_suffix('x')
+            .unwrap_or(trimmed)
+            .parse()
+            .map(ZoomRatio)
+            .map_err(|_| Error::UnsupportedName("zoom ratio".to_string(), s.to_string()))
+    }
+}
@@ -0,0 +1,452 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! Access to cameras owned by the tiny2d daemon.
+//!
+//! The daemon speaks newline-delimited JSON-RPC 2.0 over a Unix socket. Camera
+//! methods are named after the `OBSBotWebCam` methods and take
+//! `{"camera": <hint>, "value": <argument>}`; methods with several arguments
+//! take them as an array. The camera hint is matched against the device path,
+//! name, bus info and serial number, as with `Camera::new`.
+
+use crate::{
+    fov, AIMode, CameraState, CameraStatus, CtrlRange, Error, ExposureMode, FOVMode, FocusMode,
+    FovCalibration, OBSBotWebCam, PowerLineFrequency, SoftLimits, TrackingMode,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::{thread, time::Duration};
+
+/// `$XDG_RUNTIME_DIR/tiny2d.sock`, falling back to the temp directory.
+pub fn default_socket_path() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("tiny2d.sock")
+}
+
+/// A JSON-RPC request, as read by the daemon.
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Params,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Params {
+    pub camera: Option<String>,
+    #[serde(default)]
+    pub value: Value,
+}
+
+/// JSON-RPC error codes used by the daemon.
+pub const PARSE_ERROR: i32 = -32700;
+pub const METHOD_NOT_FOUND: i32 = -32601;
+pub const INTERNAL_ERROR: i32 = -32603;
+pub const CAMERA_ERROR: i32 = -32000;
+
+/// Build a JSON-RPC response line.
+pub fn response(id: Value, result: Result<Value, (i32, String)>) -> String {
+    let body = match result {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        Err((code, message)) => {
+            json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+        }
+    };
+    body.to_string()
+}
+
+fn arg<T: DeserializeOwned>(value: Value) -> Result<T, Error> {
+    serde_json::from_value(value).map_err(|e| Error::Remote(format!("invalid params: {}", e)))
+}
+
+fn ret<T: Serialize>(value: T) -> Result<Value, Error> {
+    serde_json::to_value(value).map_err(|e| Error::Remote(e.to_string()))
+}
+
+/// Run a camera method for a request. Returns `None` if `method` isn't a
+/// camera method.
+pub fn dispatch(
+    camera: &(impl OBSBotWebCam + ?Sized),
+    method: &str,
+    value: Value,
+) -> Option<Result<Value, Error>> {
+    let result = match method {
+        "set_ai_mode" => arg(value).and_then(|v| ret(camera.set_ai_mode(v)?)),
+        "get_ai_mode" => camera.get_ai_mode().and_then(ret),
+        "set_hdr_mode" => arg(value).and_then(|v| ret(camera.set_hdr_mode(v)?)),
+        "set_exposure_mode" => arg(value).and_then(|v| ret(camera.set_exposure_mode(v)?)),
+        "get_exposure_mode" => camera.get_exposure_mode().and_then(ret),
+        "set_fov" => arg(value).and_then(|v| ret(camera.set_fov(v)?)),
+        "get_fov_mode" => camera.get_fov_mode().and_then(ret),
+        "fov_model" => camera.fov_calibration().and_then(|c| ret(c.model)),
+        "set_tracking_mode" => arg(value).and_then(|v| ret(camera.set_tracking_mode(v)?)),
+        "set_auto_zoom" => arg(value).and_then(|v| ret(camera.set_auto_zoom(v)?)),
+        "set_target_lock" => arg(value).and_then(|v| ret(camera.set_target_lock(v)?)),
+        "get_status" => camera.get_status().and_then(ret),
+        "get_state" => camera.get_state().and_then(ret),
+        "get_power_line_frequency" => camera.get_power_line_frequency().and_then(ret),
+        "set_power_line_frequency" => {
+            arg(value).and_then(|v| ret(camera.set_power_line_frequency(v)?))
+        }
+        "get_mirror" => camera.get_mirror().and_then(ret),
+        "set_mirror" => arg(value).and_then(|v| ret(camera.set_mirror(v)?)),
+        "get_flip" => camera.get_flip().and_then(ret),
+        "set_flip" => arg(value).and_then(|v| ret(camera.set_flip(v)?)),
+        "get_brightness" => camera.get_brightness().and_then(ret),
+        "set_brightness" => arg(value).and_then(|v| ret(camera.set_brightness(v)?)),
+        "query_brightness_range" => camera.query_brightness_range().and_then(ret),
+        "get_contrast" => camera.get_contrast().and_then(ret),
+        "set_contrast" => arg(value).and_then(|v| ret(camera.set_contrast(v)?)),
+        "query_contrast_range" => camera.query_contrast_range().and_then(ret),
+        "get_saturation" => camera.get_saturation().and_then(ret),
+        "set_saturation" => arg(value).and_then(|v| ret(camera.set_saturation(v)?)),
+        "query_saturation_range" => camera.query_saturation_range().and_then(ret),
+        "get_sharpness" => camera.get_sharpness().and_then(ret),
+        "set_sharpness" => arg(value).and_then(|v| ret(camera.set_sharpness(v)?)),
+        "query_sharpness_range" => camera.query_sharpness_range().and_then(ret),
+        "get_focus_mode" => camera.get_focus_mode().and_then(ret),
+        "set_focus_mode" => arg(value).and_then(|v| ret(camera.set_focus_mode(v)?)),
+        "get_focus" => camera.get_focus().and_then(ret),
+        "set_focus" => arg(value).and_then(|v| ret(camera.set_focus(v)?)),
+        "query_focus_range" => camera.query_focus_range().and_then(ret),
+        "get_pan" => camera.get_pan().and_then(ret),
+        "set_pan" => arg(value).and_then(|v| ret(camera.set_pan(v)?)),
+        "get_tilt" => camera.get_tilt().and_then(ret),
+        "set_tilt" => arg(value).and_then(|v| ret(camera.set_tilt(v)?)),
+        "get_zoom" => camera.get_zoom().and_then(ret),
+        "set_zoom" => arg(value).and_then(|v| ret(camera.set_zoom(v)?)),
+        "pan_relative" => arg(value).and_then(|v| ret(camera.pan_relative(v)?)),
+        "tilt_relative" => arg(value).and_then(|v| ret(camera.tilt_relative(v)?)),
+        "zoom_relative" => arg(value).and_then(|v| ret(camera.zoom_relative(v)?)),
+        "query_pan_range" => camera.query_pan_range().and_then(ret),
+        "query_tilt_range" => camera.query_tilt_range().and_then(ret),
+        "query_zoom_range" => camera.query_zoom_range().and_then(ret),
+        "get_soft_limits" => camera.get_soft_limits().and_then(ret),
+        "set_soft_limits" => arg(value).and_then(|v| ret(camera.set_soft_limits(v)?)),
+        "move_continuous" => arg::<(f64, f64, f64)>(value)
+            .and_then(|(pan, tilt, zoom)| ret(camera.move_continuous(pan, tilt, zoom)?)),
+        "stop" => camera.stop().and_then(ret),
+        "send_cmd" => arg::<(u8, u8, Vec<u8>)>(value)
+            .and_then(|(unit, selector, cmd)| ret(camera.send_cmd(unit, selector, &cmd)?)),
+        "read_selector" => arg(value)
+            .and_then(|selector| camera.read_selector(selector))
+            .and_then(|data| ret(data.to_vec())),
+        _ => return None,
+    };
+    Some(result)
+}
+
+struct Connection {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    next_id: u64,
+}
+
+/// A camera owned by the tiny2d daemon.
+pub struct RemoteCamera {
+    camera: String,
+    conn: Mutex<Connection>,
+}
+
+impl RemoteCamera {
+    /// Connect to the daemon at `socket`, addressing the camera matching `hint`.
+    pub fn connect(socket: impl AsRef<Path>, hint: &str) -> Result<Self, Error> {
+        let writer = UnixStream::connect(socket)?;
+        let reader = BufReader::new(writer.try_clone()?);
+        Ok(RemoteCamera {
+            camera: hint.to_string(),
+            conn: Mutex::new(Connection {
+                reader,
+                writer,
+                next_id: 1,
+            }),
+        })
+    }
+
+    /// Connect to the daemon, retrying every `interval` until the camera
+    /// answers. Prints a message to stderr on the first failure, then
+    /// silently retries.
+    pub fn wait_for(socket: impl AsRef<Path>, hint: &str, interval: Duration) -> Self {
+        let mut warned = false;
+        loop {
+            match Self::connect(&socket, hint).and_then(|cam| cam.get_status().map(|_| cam)) {
+                Ok(cam) => {
+                    if warned {
+                        eprintln!("Camera \"{}\" found.", hint);
+                    }
+                    return cam;
+                }
+                Err(e) if !warned => {
+                    eprintln!("Camera \"{}\" not available ({}), waiting...", hint, e);
+                    warned = true;
+                }
+                Err(_) => {}
+            }
+            thread::sleep(interval);
+        }
+    }
+
+    /// Call a daemon method with `value` as its argument.
+    pub fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        value: impl Serialize,
+    ) -> Result<T, Error> {
+        let mut conn = self.conn.lock().unwrap();
+        let id = conn.next_id;
+        conn.next_id += 1;
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": {"camera": self.camera, "value": value},
+        });
+        writeln!(conn.writer, "{}", request)?;
+
+        let mut line = String::new();
+        if conn.reader.read_line(&mut line)? == 0 {
+            return Err(Error::Remote("daemon closed the connection".to_string()));
+        }
+        let mut response: Value =
+            serde_json::from_str(&line).map_err(|e| Error::Remote(e.to_string()))?;
+        if let Some(error) = response.get("error") {
+            let message = error["message"].as_str().unwrap_or("unknown error");
+            return Err(Error::Remote(message.to_string()));
+        }
+        serde_json::from_value(response["result"].take()).map_err(|e| Error::Remote(e.to_string()))
+    }
+}
+
+impl OBSBotWebCam for RemoteCamera {
+    fn set_ai_mode(&self, mode: AIMode) -> Result<(), Error> {
+        self.call("set_ai_mode", mode)
+    }
+
+    fn get_ai_mode(&self) -> Result<AIMode, Error> {
+        self.call("get_ai_mode", ())
+    }
+
+    fn set_hdr_mode(&self, mode: bool) -> Result<(), Error> {
+        self.call("set_hdr_mode", mode)
+    }
+
+    fn set_exposure_mode(&self, mode: ExposureMode) -> Result<(), Error> {
+        self.call("set_exposure_mode", mode)
+    }
+
+    fn get_exposure_mode(&self) -> Result<Option<ExposureMode>, Error> {
+        self.call("get_exposure_mode", ())
+    }
+
+    fn set_fov(&self, mode: FOVMode) -> Result<(), Error> {
+        self.call("set_fov", mode)
+    }
+
+    fn get_fov_mode(&self) -> Result<Option<FOVMode>, Error> {
+        self.call("get_fov_mode", ())
+    }
+
+    fn fov_calibration(&self) -> Result<&'static FovCalibration, Error> {
+        let model: String = self.call("fov_model", ())?;
+        fov::calibration(&model)
+    }
+
+    fn set_tracking_mode(&self, mode: TrackingMode) -> Result<(), Error> {
+        self.call("set_tracking_mode", mode)
+    }
+
+    fn set_auto_zoom(&self, on: bool) -> Result<(), Error> {
+        self.call("set_auto_zoom", on)
+    }
+
+    fn set_target_lock(&self, on: bool) -> Result<(), Error> {
+        self.call("set_target_lock", on)
+    }
+
+    fn get_status(&self) -> Result<CameraStatus, Error> {
+        self.call("get_status", ())
+    }
+
+    fn get_state(&self) -> Result<CameraState, Error> {
+        self.call("get_state", ())
+    }
+
+    fn get_power_line_frequency(&self) -> Result<PowerLineFrequency, Error> {
+        self.call("get_power_line_frequency", ())
+    }
+
+    fn set_power_line_frequency(&self, freq: PowerLineFrequency) -> Result<(), Error> {
+        self.call("set_power_line_frequency", freq)
+    }
+
+    fn get_mirror(&self) -> Result<bool, Error> {
+        self.call("get_mirror", ())
+    }
+
+    fn set_mirror(&self, on: bool) -> Result<(), Error> {
+        self.call("set_mirror", on)
+    }
+
+    fn get_flip(&self) -> Result<bool, Error> {
+        self.call("get_flip", ())
+    }
+
+    fn set_flip(&self, on: bool) -> Result<(), Error> {
+        self.call("set_flip", on)
+    }
+
+    fn get_brightness(&self) -> Result<i32, Error> {
+        self.call("get_brightness", ())
+    }
+
+    fn set_brightness(&self, value: i32) -> Result<(), Error> {
+        self.call("set_brightness", value)
+    }
+
+    fn query_brightness_range(&self) -> Result<CtrlRange, Error> {
+        self.call("query_brightness_range", ())
+    }
+
+    fn get_contrast(&self) -> Result<i32, Error> {
+        self.call("get_contrast", ())
+    }
+
+    fn set_contrast(&self, value: i32) -> Result<(), Error> {
+        self.call("set_contrast", value)
+    }
+
+    fn query_contrast_range(&self) -> Result<CtrlRange, Error> {
+        self.call("query_contrast_range", ())
+    }
+
+    fn get_saturation(&self) -> Result<i32, Error> {
+        self.call("get_saturation", ())
+    }
+
+    fn set_saturation(&self, value: i32) -> Result<(), Error> {
+        self.call("set_saturation", value)
+    }
+
+    fn query_saturation_range(&self) -> Result<CtrlRange, Error> {
+        self.call("query_saturation_range", ())
+    }
+
+    fn get_sharpness(&self) -> Result<i32, Error> {
+        self.call("get_sharpness", ())
+    }
+
+    fn set_sharpness(&self, value: i32) -> Result<(), Error> {
+        self.call("set_sharpness", value)
+    }
+
+    fn query_sharpness_range(&self) -> Result<CtrlRange, Error> {
+        self.call("query_sharpness_range", ())
+    }
+
+    fn get_focus_mode(&self) -> Result<FocusMode, Error> {
+        self.call("get_focus_mode", ())
+    }
+
+    fn set_focus_mode(&self, mode: FocusMode) -> Result<(), Error> {
+        self.call("set_focus_mode", mode)
+    }
+
+    fn get_focus(&self) -> Result<i32, Error> {
+        self.call("get_focus", ())
+    }
+
+    fn set_focus(&self, value: i32) -> Result<(), Error> {
+        self.call("set_focus", value)
+    }
+
+    fn query_focus_range(&self) -> Result<CtrlRange, Error> {
+        self.call("query_focus_range", ())
+    }
+
+    fn get_pan(&self) -> Result<i32, Error> {
+        self.call("get_pan", ())
+    }
+
+    fn set_pan(&self, value: i32) -> Result<(), Error> {
+        self.call("set_pan", value)
+    }
+
+    fn get_tilt(&self) -> Result<i32, Error> {
+        self.call("get_tilt", ())
+    }
+
+    fn set_tilt(&self, value: i32) -> Result<(), Error> {
+        self.call("set_tilt", value)
+    }
+
+    fn get_zoom(&self) -> Result<i32, Error> {
+        self.call("get_zoom", ())
+    }
+
+    fn set_zoom(&self, value: i32) -> Result<(), Error> {
+        self.call("set_zoom", value)
+    }
+
+    fn pan_relative(&self, delta: i32) -> Result<(), Error> {
+        self.call("pan_relative", delta)
+    }
+
+    fn tilt_relative(&self, delta: i32) -> Result<(), Error> {
+        self.call("tilt_relative", delta)
+    }
+
+    fn zoom_relative(&self, delta: i32) -> Result<(), Error> {
+        self.call("zoom_relative", delta)
+    }
+
+    fn query_pan_range(&self) -> Result<CtrlRange, Error> {
+        self.call("query_pan_range", ())
+    }
+
+    fn query_tilt_range(&self) -> Result<CtrlRange, Error> {
+        self.call("query_tilt_range", ())
+    }
+
+    fn query_zoom_range(&self) -> Result<CtrlRange, Error> {
+        self.call("query_zoom_range", ())
+    }
+
+    fn get_soft_limits(&self) -> Result<SoftLimits, Error> {
+        self.call("get_soft_limits", ())
+    }
+
+    fn set_soft_limits(&self, limits: SoftLimits) -> Result<(), Error> {
+        self.call("set_soft_limits", limits)
+    }
+
+    fn move_continuous(
+        &self,
+        pan_speed: f64,
+        tilt_speed: f64,
+        zoom_speed: f64,
+    ) -> Result<(), Error> {
+        self.call("move_continuous", (pan_speed, tilt_speed, zoom_speed))
+    }
+
+    fn stop(&self) -> Result<(), Error> {
+        self.call("stop", ())
+    }
+
+    fn send_cmd(&self, unit: u8, selector: u8, cmd: &[u8]) -> Result<(), Error> {
+        self.call("send_cmd", (unit, selector, cmd))
+    }
+
+    fn read_selector(&self, selector: u8) -> Result<[u8; 60], Error> {
+        let data: Vec<u8> = self.call("read_selector", selector)?;
+        data.try_into()
+            .map_err(|_| Error::Remote("read_selector returned the wrong length".to_string()))
+    }
+}
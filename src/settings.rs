@@ -0,0 +1,337 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! The registry of camera settings.
+//!
+//! Two tables hold everything the crate knows about a setting:
+//!
+//! * [`XU_COMMANDS`], the protocol level: the unit 2 selector 0x06 command
+//!   byte, how many value bytes follow it, and where the value reads back in
+//!   the status block. It lives in `tiny2_proto::xu`, where the command
+//!   builders and [`CameraStatus::decode`] use it; the simulator and the
+//!   capture decoder do too.
+//! * [`SETTINGS`], the user level: name, type, allowed values and units, and
+//!   the `OBSBotWebCam` methods that set and read it. The CLI `set`/`get`
+//!   subcommands, the OSC routes and the GUI controls are generated from it.
+//!
+//! Adding a setting means adding its trait methods and one entry here.
+//!
+//! [`CameraStatus::decode`]: crate::CameraStatus::decode
+
+use crate::{
+    AIMode, CtrlRange, Error, ExposureMode, FOVMode, FocusMode, OBSBotWebCam, PowerLineFrequency,
+    TrackingMode,
+};
+
+pub use tiny2_proto::xu::{
+    status_command_at, xu_command, XuCommand, AI_MODE, AUTO_ZOOM, FACE_EXPOSURE, FACE_FOCUS, FLIP,
+    FOV, HDR, MIRROR, TARGET_LOCK, TRACKING_MODE, XU_COMMANDS,
+};
+
+/// How a setting's value is chosen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    /// On or off.
+    Toggle,
+    /// One of [`Setting::choices`].
+    Choice,
+    /// An integer, usually within a range the device reports.
+    Range,
+}
+
+/// The value of a setting. Choices are indices into [`Setting::choices`].
+///
+/// Any setting accepts an `Int` too, the way OSC sends values: nonzero for
+/// on, and the index for a choice.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int(i32),
+    Choice(usize),
+}
+
+impl Value {
+    fn as_int(self) -> i32 {
+        match self {
+            Value::Bool(b) => b.into(),
+            Value::Int(i) => i,
+            Value::Choice(i) => i as i32,
+        }
+    }
+}
+
+/// A Rust type that setting values convert from and to.
+trait SettingType: Sized + Into<Value> {
+    const KIND: Kind;
+    fn choices() -> Vec<String> {
+        Vec::new()
+    }
+    fn parse(s: &str) -> Result<Self, Error>;
+    fn from_value(value: Value) -> Result<Self, Error>;
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl SettingType for bool {
+    const KIND: Kind = Kind::Toggle;
+
+    fn parse(s: &str) -> Result<Self, Error> {
+        match s.to_ascii_lowercase().as_str() {
+            "on" | "true" | "1" | "yes" => Ok(true),
+            "off" | "false" | "0" | "no" => Ok(false),
+            _ => Err(Error::UnsupportedName(
+                "on/off value".to_string(),
+                s.to_string(),
+            )),
+        }
+    }
+
+    fn from_value(value: Value) -> Result<Self, Error> {
+        Ok(value.as_int() != 0)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(i: i32) -> Self {
+        Value::Int(i)
+    }
+}
+
+impl SettingType for i32 {
+    const KIND: Kind = Kind::Range;
+
+    fn parse(s: &str) -> Result<Self, Error> {
+        s.parse()
+            .map_err(|_| Error::UnsupportedName("integer".to_string(), s.to_string()))
+    }
+
+    fn from_value(value: Value) -> Result<Self, Error> {
+        Ok(value.as_int())
+    }
+}
+
+/// Choice types: enums with an `ALL` list, `Display` names and `FromStr`.
+macro_rules! choice_type {
+    ($($ty:ident),*) => {$(
+        impl From<$ty> for Value {
+            fn from(v: $ty) -> Self {
+                Value::Choice(
+                    $ty::ALL
+                        .iter()
+                        .position(|x| *x == v)
+                        .expect(concat!("every ", stringify!($ty), " is in ALL")),
+                )
+            }
+        }
+
+        impl SettingType for $ty {
+            const KIND: Kind = Kind::Choice;
+
+            fn choices() -> Vec<String> {
+                $ty::ALL.iter().map(|v| v.to_string()).collect()
+            }
+
+            fn parse(s: &str) -> Result<Self, Error> {
+                Ok(s.parse()?)
+            }
+
+            fn from_value(value: Value) -> Result<Self, Error> {
+                let i = value.as_int();
+                usize::try_from(i)
+                    .ok()
+                    .and_then(|i| $ty::ALL.get(i).copied())
+                    .ok_or_else(|| Error::UnsupportedIntValue(stringify!($ty).to_string(), i))
+            }
+        }
+    )*};
+}
+
+choice_type!(
+    AIMode,
+    TrackingMode,
+    FOVMode,
+    ExposureMode,
+    PowerLineFrequency,
+    FocusMode
+);
+
+type Getter = fn(&dyn OBSBotWebCam) -> Result<Value, Error>;
+type RangeQuery = fn(&dyn OBSBotWebCam) -> Result<CtrlRange, Error>;
+
+/// A user-facing camera setting.
+#[derive(Debug)]
+pub struct Setting {
+    /// The name used by the CLI, e.g. `ai-mode`.
+    pub name: &'static str,
+    /// A short label for GUI controls.
+    pub label: &'static str,
+    /// One line of help.
+    pub help: &'static str,
+    pub kind: Kind,
+    pub units: Option<&'static str>,
+    /// The OSC address that sets it, as `<address> <camera> <int>`.
+    pub osc: Option<&'static str>,
+    /// The selector 0x06 command it sends, if it is a single one.
+    pub xu: Option<&'static XuCommand>,
+    choices: fn() -> Vec<String>,
+    parse: fn(&str) -> Result<Value, Error>,
+    set: fn(&dyn OBSBotWebCam, Value) -> Result<(), Error>,
+    get: Option<Getter>,
+    range: Option<RangeQuery>,
+}
+
+impl Setting {
+    /// Names of the allowed values of a choice, in index order. Empty for
+    /// other kinds.
+    pub fn choices(&self) -> Vec<String> {
+        (self.choices)()
+    }
+
+    /// Parse a value as typed on the command line, e.g. "upper-body" or "on".
+    pub fn parse(&self, s: &str) -> Result<Value, Error> {
+        (self.parse)(s)
+    }
+
+    /// Format a value the way [`Setting::parse`] accepts it.
+    pub fn format(&self, value: Value) -> String {
+        match (self.kind, value) {
+            (Kind::Choice, Value::Choice(i)) => self
+                .choices()
+                .get(i)
+                .cloned()
+                .unwrap_or_else(|| i.to_string()),
+            (Kind::Toggle, v) => if v.as_int() != 0 { "on" } else { "off" }.to_string(),
+            (_, v) => v.as_int().to_string(),
+        }
+    }
+
+    pub fn set(&self, camera: &dyn OBSBotWebCam, value: Value) -> Result<(), Error> {
+        (self.set)(camera, value)
+    }
+
+    /// Whether the camera can report the current value.
+    pub fn is_readable(&self) -> bool {
+        self.get.is_some()
+    }
+
+    /// Read the current value, or `None` for settings that can only be set.
+    pub fn get(&self, camera: &dyn OBSBotWebCam) -> Result<Option<Value>, Error> {
+        self.get.map(|get| get(camera)).transpose()
+    }
+
+    /// The device's range for a `Range` setting, if it reports one.
+    pub fn range(&self, camera: &dyn OBSBotWebCam) -> Result<Option<CtrlRange>, Error> {
+        self.range.map(|range| range(camera)).transpose()
+    }
+}
+
+macro_rules! opt {
+    () => {
+        None
+    };
+    ($e:expr) => {
+        Some($e)
+    };
+}
+
+/// One [`Setting`] entry. `get` names a trait method, `status` a field of
+/// [`CameraStatus`](crate::CameraStatus) for settings read from the status
+/// block.
+macro_rules! setting {
+    (
+        $name:literal: $ty:ty, $label:literal, $help:literal,
+        set: $set:ident
+        $(, get: $get:ident)?
+        $(, status: $field:ident)?
+        $(, range: $range:ident)?
+        $(, xu: $xu:ident)?
+        $(, osc: $osc:literal)?
+        $(, units: $units:literal)?
+    ) => {
+        Setting {
+            name: $name,
+            label: $label,
+            help: $help,
+            kind: <$ty as SettingType>::KIND,
+            units: opt!($($units)?),
+            osc: opt!($($osc)?),
+            xu: opt!($(&$xu)?),
+            choices: <$ty as SettingType>::choices,
+            parse: |s| <$ty as SettingType>::parse(s).map(Value::from),
+            set: |camera, value| camera.$set(<$ty as SettingType>::from_value(value)?),
+            get: opt!(
+                $(|camera: &dyn OBSBotWebCam| camera.$get().map(Value::from))?
+                $(|camera: &dyn OBSBotWebCam| camera.get_status().map(|s| Value::from(s.$field)))?
+            ),
+            range: opt!($(|camera: &dyn OBSBotWebCam| camera.$range())?),
+        }
+    };
+}
+
+/// Every setting, in the order the GUI lays them out.
+pub static SETTINGS: [Setting; 19] = [
+    setting!("ai-mode": AIMode, "AI Mode", "AI tracking mode",
+        set: set_ai_mode, get: get_ai_mode, xu: AI_MODE,
+        osc: "/OBSBOT/WebCam/Tiny/SetAiMode"),
+    setting!("tracking-mode": TrackingMode, "Tracking", "Tracking framing and responsiveness",
+        set: set_tracking_mode, xu: TRACKING_MODE,
+        osc: "/OBSBOT/WebCam/Tiny/SetTrackingMode"),
+    setting!("auto-zoom": bool, "Auto Zoom", "Automatic zoom while tracking",
+        set: set_auto_zoom, xu: AUTO_ZOOM,
+        osc: "/OBSBOT/WebCam/Tiny/SetAutoZoom"),
+    setting!("target-lock": bool, "Lock Target", "Lock tracking onto the current person",
+        set: set_target_lock, xu: TARGET_LOCK,
+        osc: "/OBSBOT/WebCam/Tiny/SetTargetLock"),
+    setting!("exposure": ExposureMode, "Exposure", "Exposure mode",
+        set: set_exposure_mode),
+    setting!("fov": FOVMode, "Field of View", "Field of view",
+        set: set_fov, xu: FOV),
+    setting!("power-line": PowerLineFrequency, "Anti-flicker",
+        "Power line frequency to cancel flicker for",
+        set: set_power_line_frequency, get: get_power_line_frequency),
+    setting!("focus-mode": FocusMode, "Focus", "Focus mode",
+        set: set_focus_mode, get: get_focus_mode),
+    setting!("hdr": bool, "HDR", "High dynamic range",
+        set: set_hdr_mode, status: hdr_on, xu: HDR),
+    setting!("mirror": bool, "Mirror", "Mirror the image horizontally",
+        set: set_mirror, get: get_mirror,
+        osc: "/OBSBOT/WebCam/General/SetMirror"),
+    setting!("flip": bool, "Flip", "Flip the image vertically",
+        set: set_flip, get: get_flip,
+        osc: "/OBSBOT/WebCam/General/SetFlip"),
+    setting!("brightness": i32, "Brightness", "Image brightness",
+        set: set_brightness, get: get_brightness, range: query_brightness_range),
+    setting!("contrast": i32, "Contrast", "Image contrast",
+        set: set_contrast, get: get_contrast, range: query_contrast_range),
+    setting!("saturation": i32, "Saturation", "Color saturation",
+        set: set_saturation, get: get_saturation, range: query_saturation_range),
+    setting!("sharpness": i32, "Sharpness", "Image sharpness",
+        set: set_sharpness, get: get_sharpness, range: query_sharpness_range),
+    setting!("focus": i32, "Focus Position", "Manual focus position",
+        set: set_focus, get: get_focus, range: query_focus_range),
+    setting!("pan": i32, "Pan", "Absolute pan position",
+        set: set_pan, get: get_pan, range: query_pan_range,
+        osc: "/pan", units: "arcsec"),
+    setting!("tilt": i32, "Tilt", "Absolute tilt position",
+        set: set_tilt, get: get_tilt, range: query_tilt_range,
+        osc: "/tilt", units: "arcsec"),
+    setting!("zoom": i32, "Zoom", "Absolute zoom position",
+        set: set_zoom, get: get_zoom, range: query_zoom_range,
+        osc: "/zoom"),
+];
+
+/// The setting called `name`.
+pub fn lookup(name: &str) -> Result<&'static Setting, Error> {
+    SETTINGS
+        .iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| Error::UnsupportedName("setting".to_string(), name.to_string()))
+}
+
+/// The setting an OSC message to `address` sets.
+pub fn osc_route(address: &str) -> Option<&'static Setting> {
+    SETTINGS.iter().find(|s| s.osc == Some(address))
+}
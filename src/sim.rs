@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! A simulated camera, for trying the tools and library without hardware.
+//!
+//! It models what this crate already knows about the protocol: the
+//! extension-unit commands whose effect on the 0x06 status block is
+//! understood (see [`settings::XU_COMMANDS`]), and the V4L2 controls with
+//! plausible ranges. Commands it doesn't know are accepted and ignored, and
+//! so are the status bytes of unverified ones, so the simulator doesn't
+//! confirm a guessed offset.
+
+use crate::settings;
+use crate::usbio::{self, UvcUsbIo, V4l2CtrlRange};
+use errno::Errno;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+const EINVAL: i32 = 22;
+
+/// The model the simulator imitates, for its field of view calibration.
+pub const MODEL: &str = "OBSBOT Tiny 2";
+
+/// V4L2 controls: id, minimum, maximum, step, default.
+const CONTROLS: [(u32, i32, i32, i32, i32); 12] = [
+    (usbio::V4L2_CID_BRIGHTNESS, 0, 100, 1, 50),
+    (usbio::V4L2_CID_CONTRAST, 0, 100, 1, 50),
+    (usbio::V4L2_CID_SATURATION, 0, 100, 1, 50),
+    (usbio::V4L2_CID_SHARPNESS, 0, 100, 1, 50),
+    (usbio::V4L2_CID_POWER_LINE_FREQUENCY, 0, 3, 1, 1),
+    (usbio::V4L2_CID_HFLIP, 0, 1, 1, 0),
+    (usbio::V4L2_CID_VFLIP, 0, 1, 1, 0),
+    (usbio::V4L2_CID_PAN_ABSOLUTE, -468000, 468000, 3600, 0),
+    (usbio::V4L2_CID_TILT_ABSOLUTE, -324000, 324000, 3600, 0),
+    (usbio::V4L2_CID_ZOOM_ABSOLUTE, 0, 100, 1, 0),
+    (usbio::V4L2_CID_FOCUS_ABSOLUTE, 0, 100, 1, 50),
+    (usbio::V4L2_CID_FOCUS_AUTO, 0, 1, 1, 1),
+];
+
+/// Relative controls and the absolute control they move.
+const RELATIVE: [(u32, u32); 3] = [
+    (usbio::V4L2_CID_PAN_RELATIVE, usbio::V4L2_CID_PAN_ABSOLUTE),
+    (usbio::V4L2_CID_TILT_RELATIVE, usbio::V4L2_CID_TILT_ABSOLUTE),
+    (usbio::V4L2_CID_ZOOM_RELATIVE, usbio::V4L2_CID_ZOOM_ABSOLUTE),
+];
+
+#[derive(Debug)]
+struct State {
+    /// GET_CUR buffers of unit 2, by selector.
+    buffers: BTreeMap<u8, [u8; 60]>,
+    controls: BTreeMap<u32, (i32, V4l2CtrlRange)>,
+}
+
+/// A device handle backed by an in-memory model of the camera.
+#[derive(Debug)]
+pub struct SimulatedCamera {
+    state: Mutex<State>,
+}
+
+impl Default for SimulatedCamera {
+    fn default() -> Self {
+        let controls = CONTROLS
+            .iter()
+            .map(|&(id, minimum, maximum, step, default_value)| {
+                let range = V4l2CtrlRange {
+                    minimum,
+                    maximum,
+                    step,
+                    default_value,
+                };
+                (id, (default_value, range))
+            })
+            .collect();
+        SimulatedCamera {
+            state: Mutex::new(State {
+                buffers: [(0x02, [0u8; 60]), (0x06, [0u8; 60])].into(),
+                controls,
+            }),
+        }
+    }
+}
+
+impl SimulatedCamera {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl UvcUsbIo for SimulatedCamera {
+    fn info(&self) -> Result<(), Errno> {
+        println!("Card: simulated camera");
+        Ok(())
+    }
+
+    fn io(&self, unit: u8, selector: u8, query: u8, data: &mut [u8]) -> Result<(), Errno> {
+        let mut state = self.state.lock().unwrap();
+        let buffer = match state.buffers.get_mut(&selector) {
+            Some(buffer) if unit == 0x2 => buffer,
+            _ => return Err(Errno(EINVAL)),
+        };
+        match query {
+            usbio::UVC_GET_LEN if data.len() == 2 => {
+                data.copy_from_slice(&(buffer.len() as u16).to_le_bytes());
+            }
+            usbio::UVC_GET_CUR if data.len() == buffer.len() => data.copy_from_slice(buffer),
+            usbio::UVC_SET_CUR if selector == 0x06 && data.len() >= 2 => {
+                let (cmd, values) = (data[0], &data[2..]);
+                if let Some(command) = settings::xu_command(cmd).filter(|c| c.is_verified()) {
+                    for (&offset, &value) in command.status.iter().zip(values) {
+                        buffer[offset] = value;
+                    }
+                }
+            }
+            usbio::UVC_SET_CUR => {}
+            _ => return Err(Errno(EINVAL)),
+        }
+        Ok(())
+    }
+
+    fn get_ctrl(&self, id: u32) -> Result<i32, Errno> {
+        let state = self.state.lock().unwrap();
+        state
+            .controls
+            .get(&id)
+            .map(|&(value, _)| value)
+            .ok_or(Errno(EINVAL))
+    }
+
+    fn set_ctrl(&self, id: u32, value: i32) -> Result<(), Errno> {
+        let mut state = self.state.lock().unwrap();
+        let (id, value) = match RELATIVE.iter().find(|(rel, _)| *rel == id) {
+            Some(&(_, abs)) => {
+                let current = state.controls.get(&abs).ok_or(Errno(EINVAL))?.0;
+                (abs, current + value)
+            }
+            None => (id, value),
+        };
+        let (current, range) = state.controls.get_mut(&id).ok_or(Errno(EINVAL))?;
+        *current = value.clamp(range.minimum, range.maximum);
+        Ok(())
+    }
+
+    fn query_ctrl(&self, id: u32) -> Result<V4l2CtrlRange, Errno> {
+        let state = self.state.lock().unwrap();
+        state
+            .controls
+            .get(&id)
+            .map(|&(_, range)| range)
+            .ok_or(Errno(EINVAL))
+    }
+}
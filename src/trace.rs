@@ -0,0 +1,335 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! Recording of the raw protocol traffic between a `Camera` and the device.
+//!
+//! Every extension-unit query and V4L2 control call is written as one JSON
+//! object per line, e.g.
+//!
+//! ```text
+//! {"time":1718000000.123,"op":"io","unit":2,"selector":6,"query":1,"request":"0a0101","response":"0a0101"}
+//! {"time":1718000000.125,"op":"get_ctrl","id":10094856,"errno":22}
+//! ```
+//!
+//! `response`, `value` and `range` are left out when the call failed, and
+//! `errno` is left out when it succeeded.
+//!
+//! A trace can be played back with [`TraceReplay`] and [`Camera::replay`],
+//! so that a recorded session can exercise `Camera` without the hardware.
+//!
+//! [`Camera::replay`]: crate::Camera::replay
+
+use crate::usbio::{CameraHandleType, UvcUsbIo, V4l2CtrlRange};
+use crate::{CtrlRange, Error};
+use errno::Errno;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, LineWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One call into the device.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Op {
+    /// A UVC extension-unit query. `request` and `response` are the hex
+    /// encoded buffer before and after the call.
+    Io {
+        unit: u8,
+        selector: u8,
+        query: u8,
+        request: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        response: Option<String>,
+    },
+    GetCtrl {
+        id: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        value: Option<i32>,
+    },
+    SetCtrl {
+        id: u32,
+        value: i32,
+    },
+    QueryCtrl {
+        id: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        range: Option<CtrlRange>,
+    },
+}
+
+/// A line of a trace file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    /// Unix time of the call, in seconds.
+    pub time: f64,
+    #[serde(flatten)]
+    pub op: Op,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub errno: Option<i32>,
+}
+
+/// A trace file being written. Clones share the file, so several cameras can
+/// record into one trace.
+#[derive(Debug, Clone)]
+pub struct Trace(Arc<Mutex<LineWriter<File>>>);
+
+impl Trace {
+    /// Create (or truncate) a trace file.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let file = File::create(path)?;
+        Ok(Trace(Arc::new(Mutex::new(LineWriter::new(file)))))
+    }
+
+    fn record(&self, op: Op, errno: Option<&Errno>) {
+        let record = Record {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs_f64())
+                .unwrap_or_default(),
+            op,
+            errno: errno.map(|e| e.0),
+        };
+        let mut file = self.0.lock().unwrap();
+        let written = serde_json::to_writer(&mut *file, &record)
+            .map_err(std::io::Error::from)
+            .and_then(|_| file.write_all(b"\n"));
+        if let Err(e) = written {
+            eprintln!("trace: {}", e);
+        }
+    }
+}
+
+/// A device handle that records every call it passes through.
+#[derive(Debug)]
+pub struct TraceRecorder {
+    inner: Box<CameraHandleType>,
+    trace: Trace,
+}
+
+impl TraceRecorder {
+    pub(crate) fn new(inner: CameraHandleType, trace: Trace) -> Self {
+        TraceRecorder {
+            inner: Box::new(inner),
+            trace,
+        }
+    }
+}
+
+impl UvcUsbIo for TraceRecorder {
+    fn info(&self) -> Result<(), Errno> {
+        self.inner.info()
+    }
+
+    fn io(&self, unit: u8, selector: u8, query: u8, data: &mut [u8]) -> Result<(), Errno> {
+        let request = hex::encode(&data);
+        let result = self.inner.io(unit, selector, query, data);
+        let op = Op::Io {
+            unit,
+            selector,
+            query,
+            request,
+            response: result.as_ref().ok().map(|_| hex::encode(&data)),
+        };
+        self.trace.record(op, result.as_ref().err());
+        result
+    }
+
+    fn get_ctrl(&self, id: u32) -> Result<i32, Errno> {
+        let result = self.inner.get_ctrl(id);
+        let op = Op::GetCtrl {
+            id,
+            value: result.as_ref().ok().copied(),
+        };
+        self.trace.record(op, result.as_ref().err());
+        result
+    }
+
+    fn set_ctrl(&self, id: u32, value: i32) -> Result<(), Errno> {
+        let result = self.inner.set_ctrl(id, value);
+        self.trace
+            .record(Op::SetCtrl { id, value }, result.as_ref().err());
+        result
+    }
+
+    fn query_ctrl(&self, id: u32) -> Result<V4l2CtrlRange, Errno> {
+        let result = self.inner.query_ctrl(id);
+        let op = Op::QueryCtrl {
+            id,
+            range: result.as_ref().ok().map(|&r| r.into()),
+        };
+        self.trace.record(op, result.as_ref().err());
+        result
+    }
+}
+
+/// A device handle that plays back a trace.
+///
+/// Each call must match the next recorded one: same operation, same
+/// arguments and, for extension-unit queries, the same request bytes. It then
+/// gets the recorded response or errno. A call that doesn't match, or one
+/// made after the trace has run out, panics, and so does dropping the replay
+/// before every recorded call has been made. Timestamps are ignored.
+#[derive(Debug)]
+pub struct TraceReplay {
+    records: Vec<Record>,
+    /// Index of the next record to play back.
+    position: Mutex<usize>,
+}
+
+impl TraceReplay {
+    pub fn new(records: impl IntoIterator<Item = Record>) -> Self {
+        TraceReplay {
+            records: records.into_iter().collect(),
+            position: Mutex::new(0),
+        }
+    }
+
+    /// Read a trace file written by [`Trace`]. Blank lines are skipped.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut records = Vec::new();
+        for (n, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = serde_json::from_str(&line)
+                .map_err(|e| Error::TraceFormat(format!("line {}: {}", n + 1, e)))?;
+            records.push(record);
+        }
+        Ok(Self::new(records))
+    }
+
+    /// Number of recorded calls that haven't been made yet.
+    pub fn remaining(&self) -> usize {
+        self.records.len() - *self.position.lock().unwrap()
+    }
+
+    /// Take the next record, checking it against the call being made.
+    fn next(&self, op: &Op) -> Record {
+        let mut position = self.position.lock().unwrap();
+        let Some(record) = self.records.get(*position).cloned() else {
+            panic!("replay: call after end of trace: {:?}", op);
+        };
+        *position += 1;
+        if !matches_call(&record.op, op) {
+            panic!(
+                "replay: call {} doesn't match the trace\n  expected: {:?}\n       got: {:?}",
+                position, record.op, op
+            );
+        }
+        record
+    }
+}
+
+/// Whether a recorded operation is the same call as `op`, ignoring the
+/// recorded results.
+fn matches_call(recorded: &Op, op: &Op) -> bool {
+    match (recorded, op) {
+        (
+            Op::Io {
+                unit,
+                selector,
+                query,
+                request,
+                ..
+            },
+            Op::Io {
+                unit: u,
+                selector: s,
+                query: q,
+                request: r,
+                ..
+            },
+        ) => unit == u && selector == s && query == q && request.eq_ignore_ascii_case(r),
+        (Op::GetCtrl { id, .. }, Op::GetCtrl { id: i, .. }) => id == i,
+        (Op::SetCtrl { id, value }, Op::SetCtrl { id: i, value: v }) => id == i && value == v,
+        (Op::QueryCtrl { id, .. }, Op::QueryCtrl { id: i, .. }) => id == i,
+        _ => false,
+    }
+}
+
+fn recorded_errno(record: &Record) -> Errno {
+    // A record without a result or errno is malformed; report EIO.
+    Errno(record.errno.unwrap_or(5))
+}
+
+impl Drop for TraceReplay {
+    fn drop(&mut self) {
+        // A mismatch has already panicked (and poisoned the lock).
+        if thread::panicking() {
+            return;
+        }
+        let remaining = self.remaining();
+        if remaining > 0 {
+            panic!("replay: {} recorded call(s) were never made", remaining);
+        }
+    }
+}
+
+impl UvcUsbIo for TraceReplay {
+    fn info(&self) -> Result<(), Errno> {
+        println!("Card: trace replay");
+        Ok(())
+    }
+
+    fn io(&self, unit: u8, selector: u8, query: u8, data: &mut [u8]) -> Result<(), Errno> {
+        let record = self.next(&Op::Io {
+            unit,
+            selector,
+            query,
+            request: hex::encode(&data),
+            response: None,
+        });
+        let Op::Io {
+            response: Some(response),
+            ..
+        } = &record.op
+        else {
+            return Err(recorded_errno(&record));
+        };
+        let response = hex::decode(response)
+            .unwrap_or_else(|e| panic!("replay: bad response {:?}: {}", response, e));
+        if response.len() != data.len() {
+            panic!(
+                "replay: recorded response is {} bytes, buffer is {}",
+                response.len(),
+                data.len()
+            );
+        }
+        data.copy_from_slice(&response);
+        Ok(())
+    }
+
+    fn get_ctrl(&self, id: u32) -> Result<i32, Errno> {
+        match self.next(&Op::GetCtrl { id, value: None }) {
+            Record {
+                op: Op::GetCtrl {
+                    value: Some(value), ..
+                },
+                ..
+            } => Ok(value),
+            record => Err(recorded_errno(&record)),
+        }
+    }
+
+    fn set_ctrl(&self, id: u32, value: i32) -> Result<(), Errno> {
+        match self.next(&Op::SetCtrl { id, value }) {
+            Record { errno: None, .. } => Ok(()),
+            record => Err(recorded_errno(&record)),
+        }
+    }
+
+    fn query_ctrl(&self, id: u32) -> Result<V4l2CtrlRange, Errno> {
+        match self.next(&Op::QueryCtrl { id, range: None }) {
+            Record {
+                op: Op::QueryCtrl {
+                    range: Some(range), ..
+                },
+                ..
+            } => Ok(range.into()),
+            record => Err(recorded_errno(&record)),
+        }
+    }
+}
@@ -2,7 +2,6 @@
 
 use enum_dispatch::enum_dispatch;
 use errno::Errno;
-use nix::errno::errno;
 use nix::{ioctl_read_buf, ioctl_readwrite, ioctl_readwrite_buf};
 use std::fs::File;
 use std::os::unix::io::AsRawFd;
@@ -30,6 +29,18 @@ pub struct V4l2CtrlRange {
     pub default_value: i32,
 }
 
+/// The device handle behind a `Camera`.
+#[enum_dispatch]
+#[derive(Debug)]
+pub enum CameraHandleType {
+    CameraHandle,
+    #[cfg(feature = "trace")]
+    TraceRecorder(crate::trace::TraceRecorder),
+    #[cfg(feature = "trace")]
+    TraceReplay(crate::trace::TraceReplay),
+    SimulatedCamera(crate::sim::SimulatedCamera),
+}
+
 #[derive(Debug)]
 pub struct CameraHandle(std::fs::File);
 
@@ -52,7 +63,7 @@ impl UvcUsbIo for CameraHandle {
             }
             _ => {
                 println!("Failed");
-                Err(errno::Errno(errno()))
+                Err(errno::Errno(nix::errno::Errno::last_raw()))
             }
         }
     }
@@ -71,7 +82,7 @@ impl UvcUsbIo for CameraHandle {
         unsafe {
             match uvcioc_ctrl_query(dev.as_raw_fd(), &mut [query]) {
                 Ok(_) => Ok(()),
-                _ => Err(errno::Errno(errno())),
+                _ => Err(errno::Errno(nix::errno::Errno::last_raw())),
             }
         }
     }
@@ -83,7 +94,7 @@ impl UvcUsbIo for CameraHandle {
         unsafe {
             match vidioc_g_ctrl(dev.as_raw_fd(), &mut ctrl) {
                 Ok(_) => Ok(ctrl.value),
-                _ => Err(errno::Errno(errno())),
+                _ => Err(errno::Errno(nix::errno::Errno::last_raw())),
             }
         }
     }
@@ -95,7 +106,7 @@ impl UvcUsbIo for CameraHandle {
         unsafe {
             match vidioc_s_ctrl(dev.as_raw_fd(), &mut ctrl) {
                 Ok(_) => Ok(()),
-                _ => Err(errno::Errno(errno())),
+                _ => Err(errno::Errno(nix::errno::Errno::last_raw())),
             }
         }
     }
@@ -120,19 +131,88 @@ impl UvcUsbIo for CameraHandle {
                         default_value: qctrl.default_value,
                     })
                 }
-                _ => Err(errno::Errno(errno())),
+                _ => Err(errno::Errno(nix::errno::Errno::last_raw())),
             }
         }
     }
 }
 
-pub(crate) fn open_camera(hint: &str) -> Result<CameraHandle, crate::Error> {
-    if let Ok(file) = File::open(hint) {
-        return Ok(file.into());
+/// A V4L2 video node found while enumerating devices.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceInfo {
+    pub path: String,
+    pub card: String,
+    pub bus_info: String,
+    /// USB serial number, if the device has one.
+    pub serial: Option<String>,
+}
+
+impl DeviceInfo {
+    /// What per-camera settings are stored under: the serial number, or
+    /// the bus info for devices without one.
+    pub fn key(&self) -> &str {
+        self.serial.as_deref().unwrap_or(&self.bus_info)
     }
+}
+
+fn c_str(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .trim_end_matches('\0')
+        .to_string()
+}
 
-    if let Ok(file) = File::open("/dev/".to_owned() + hint) {
-        return Ok(file.into());
+/// Read the USB serial number of a video node from sysfs. The node's `device`
+/// link points at the USB interface, whose parent holds the serial.
+fn usb_serial(path: &std::path::Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    let serial_path = format!("/sys/class/video4linux/{}/device/../serial", name);
+    std::fs::read_to_string(serial_path)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// List the video nodes that can be opened with `open_camera`, skipping
+/// metadata nodes.
+pub(crate) fn list_devices() -> Vec<DeviceInfo> {
+    let options = MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: true,
+    };
+    let mut devices = Vec::new();
+    for path in glob_with("/dev/video*", options).unwrap().flatten() {
+        if let Ok(device) = File::open(&path) {
+            if let Ok(video_info) = v4l2_capability::new(&device) {
+                if video_info.device_caps & 0x800000 == 0 {
+                    devices.push(device_info(&path, &video_info));
+                }
+            }
+        }
+    }
+    devices
+}
+
+fn device_info(path: &std::path::Path, video_info: &v4l2_capability) -> DeviceInfo {
+    DeviceInfo {
+        path: path.display().to_string(),
+        card: c_str(&video_info.card),
+        bus_info: c_str(&video_info.bus_info),
+        serial: usb_serial(path),
+    }
+}
+
+/// Open a camera by path or by part of its name or bus info. The device
+/// info is `None` if the node doesn't answer VIDIOC_QUERYCAP.
+pub(crate) fn open_camera(hint: &str) -> Result<(CameraHandle, Option<DeviceInfo>), crate::Error> {
+    for path in [hint.to_owned(), "/dev/".to_owned() + hint] {
+        if let Ok(file) = File::open(&path) {
+            let info = v4l2_capability::new(&file)
+                .ok()
+                .map(|video_info| device_info(std::path::Path::new(&path), &video_info));
+            return Ok((file.into(), info));
+        }
     }
 
     // enumerate all cameras and check for match
@@ -149,7 +229,57 @@ pub(crate) fn open_camera(hint: &str) -> Result<CameraHandle, crate::Error> {
                     || str::from_utf8(&video_info.bus_info).unwrap().contains(hint))
                     && (video_info.device_caps & 0x800000 == 0)
                 {
-                    return Ok(device.into());
+                    let info = device_info(&path, &video_info);
+                    return Ok((device.into(), Some(info)));
+                }
+            }
+        }
+    }
+    Err(crate::Error::NoCameraFound)
+}
+
+/// Open the video capture node of a camera, by path or by part of its name
+/// or bus info. For UVC cameras this is usually the node `open_camera`
+/// finds; pass the control node's bus info to get the one grouped with it.
+#[cfg(feature = "capture")]
+pub(crate) fn open_capture_node(hint: &str) -> Result<(File, DeviceInfo), crate::Error> {
+    let can_stream = |video_info: &v4l2_capability| {
+        video_info.device_caps & V4L2_CAP_VIDEO_CAPTURE != 0
+            && video_info.device_caps & V4L2_CAP_STREAMING != 0
+    };
+    for path in [hint.to_owned(), "/dev/".to_owned() + hint] {
+        if let Ok(file) = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+        {
+            return match v4l2_capability::new(&file) {
+                Ok(video_info) if can_stream(&video_info) => {
+                    let info = device_info(std::path::Path::new(&path), &video_info);
+                    Ok((file, info))
+                }
+                _ => Err(crate::Error::NoCameraFound),
+            };
+        }
+    }
+
+    let options = MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: true,
+    };
+    for path in glob_with("/dev/video*", options).unwrap().flatten() {
+        if let Ok(device) = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+        {
+            if let Ok(video_info) = v4l2_capability::new(&device) {
+                let info = device_info(&path, &video_info);
+                if (info.card.contains(hint) || info.bus_info.contains(hint))
+                    && can_stream(&video_info)
+                {
+                    return Ok((device, info));
                 }
             }
         }
@@ -157,6 +287,11 @@ pub(crate) fn open_camera(hint: &str) -> Result<CameraHandle, crate::Error> {
     Err(crate::Error::NoCameraFound)
 }
 
+#[cfg(feature = "capture")]
+const V4L2_CAP_VIDEO_CAPTURE: u32 = 0x00000001;
+#[cfg(feature = "capture")]
+const V4L2_CAP_STREAMING: u32 = 0x04000000;
+
 #[repr(C)]
 #[allow(non_camel_case_types)]
 #[derive(Copy, Clone, Default, Debug)]
@@ -179,7 +314,7 @@ impl v4l2_capability {
         unsafe {
             match ioctl_videoc_querycap(dev.as_raw_fd(), &mut query) {
                 Ok(_) => Ok(query[0]),
-                _ => Err(errno::Errno(errno())),
+                _ => Err(errno::Errno(nix::errno::Errno::last_raw())),
             }
         }
     }
@@ -205,14 +340,14 @@ pub struct uvc_xu_control_query {
     data: *mut u8,
 }
 
-#[allow(non_camel_case_types)]
+#[allow(non_camel_case_types, dead_code)]
 #[repr(C)]
 pub struct uvc_menu_info {
     name: [u8; 32],
     value: u32,
 }
 
-#[allow(non_camel_case_types)]
+#[allow(non_camel_case_types, dead_code)]
 #[repr(C)]
 pub struct uvc_xu_control_mapping {
     id: u32,
@@ -298,17 +433,26 @@ ioctl_readwrite!(vidioc_g_ctrl, b'V', 27, v4l2_control);
 ioctl_readwrite!(vidioc_s_ctrl, b'V', 28, v4l2_control);
 ioctl_readwrite!(vidioc_queryctrl, b'V', 36, v4l2_queryctrl);
 
+// Standard V4L2 User Control IDs
+// V4L2_CID_USER_BASE = 0x00980900
+pub const V4L2_CID_BRIGHTNESS: u32 = 0x00980900;
+pub const V4L2_CID_CONTRAST: u32 = 0x00980901;
+pub const V4L2_CID_SATURATION: u32 = 0x00980902;
+pub const V4L2_CID_HFLIP: u32 = 0x00980914;
+pub const V4L2_CID_VFLIP: u32 = 0x00980915;
+pub const V4L2_CID_POWER_LINE_FREQUENCY: u32 = 0x00980918;
+pub const V4L2_CID_SHARPNESS: u32 = 0x0098091B;
+
 // Standard V4L2 Camera Control IDs
 // V4L2_CID_CAMERA_CLASS_BASE = 0x009A0900
-#[allow(dead_code)]
+pub const V4L2_CID_PAN_RELATIVE: u32 = 0x009A0904;
+pub const V4L2_CID_TILT_RELATIVE: u32 = 0x009A0905;
 pub const V4L2_CID_PAN_ABSOLUTE: u32 = 0x009A0908;
-#[allow(dead_code)]
 pub const V4L2_CID_TILT_ABSOLUTE: u32 = 0x009A0909;
-#[allow(dead_code)]
-pub const V4L2_CID_PAN_RELATIVE: u32 = 0x009A090A;
-#[allow(dead_code)]
-pub const V4L2_CID_TILT_RELATIVE: u32 = 0x009A090B;
-#[allow(dead_code)]
+pub const V4L2_CID_FOCUS_ABSOLUTE: u32 = 0x009A090A;
+pub const V4L2_CID_FOCUS_AUTO: u32 = 0x009A090C;
 pub const V4L2_CID_ZOOM_ABSOLUTE: u32 = 0x009A090D;
-#[allow(dead_code)]
 pub const V4L2_CID_ZOOM_RELATIVE: u32 = 0x009A090E;
+pub const V4L2_CID_ZOOM_CONTINUOUS: u32 = 0x009A090F;
+pub const V4L2_CID_PAN_SPEED: u32 = 0x009A0920;
+pub const V4L2_CID_TILT_SPEED: u32 = 0x009A0921;
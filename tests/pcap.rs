@@ -0,0 +1,197 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! Reading USBPcap captures from hand-built pcap and pcapng files.
+//!
+//! Each file holds a SET_CUR of HDR on and a GET_CUR of the status buffer,
+//! both to the extension unit, and a second SET_CUR whose completion wasn't
+//! captured.
+
+use tiny2::pcap::{self, ControlRequest};
+use tiny2::proto::command;
+
+const LINKTYPE_USBPCAP: u32 = 249;
+const SET_CUR: u8 = 0x01;
+const GET_CUR: u8 = 0x81;
+
+/// A USBPcap control transfer packet: the 28-byte header, then `payload`.
+fn usbpcap(irp: u64, from_device: bool, stage: u8, payload: &[u8]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&28u16.to_le_bytes());
+    data.extend_from_slice(&irp.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes()); // USBD status
+    data.extend_from_slice(&0u16.to_le_bytes()); // URB function
+    data.push(from_device.into());
+    data.extend_from_slice(&1u16.to_le_bytes()); // bus
+    data.extend_from_slice(&3u16.to_le_bytes()); // device
+    data.push(0); // endpoint
+    data.push(2); // control transfer
+    data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    data.push(stage);
+    data.extend_from_slice(payload);
+    data
+}
+
+/// A class request to interface 0, unit 2, with its setup packet.
+fn setup(request: u8, selector: u8, length: u16, data: &[u8]) -> Vec<u8> {
+    let request_type = if request & 0x80 != 0 { 0xa1 } else { 0x21 };
+    let mut payload = vec![request_type, request, 0, selector, 0, 2];
+    payload.extend_from_slice(&length.to_le_bytes());
+    payload.extend_from_slice(data);
+    payload
+}
+
+fn status_buffer() -> Vec<u8> {
+    let mut status = vec![0u8; 60];
+    status[0] = 0x16;
+    status
+}
+
+/// The packets of the capture, each with its time in microseconds.
+fn packets() -> Vec<(u64, Vec<u8>)> {
+    let hdr = command::hdr(true).data;
+    let desk = command::ai_mode(tiny2::AIMode::Desk).data;
+    vec![
+        (
+            1_000_000,
+            usbpcap(1, false, 0, &setup(SET_CUR, 6, 60, &hdr)),
+        ),
+        (1_000_100, usbpcap(1, true, 3, &[])),
+        (1_500_000, usbpcap(2, false, 0, &setup(GET_CUR, 6, 60, &[]))),
+        (1_500_100, usbpcap(2, true, 3, &status_buffer())),
+        (
+            2_000_000,
+            usbpcap(3, false, 0, &setup(SET_CUR, 6, 60, &desk)),
+        ),
+    ]
+}
+
+type Put16 = fn(u16) -> [u8; 2];
+type Put32 = fn(u32) -> [u8; 4];
+
+/// Writers for 16- and 32-bit fields in the file's byte order.
+fn byte_order(big_endian: bool) -> (Put16, Put32) {
+    if big_endian {
+        (u16::to_be_bytes, u32::to_be_bytes)
+    } else {
+        (u16::to_le_bytes, u32::to_le_bytes)
+    }
+}
+
+fn pcap_file(big_endian: bool) -> Vec<u8> {
+    let (u16, u32) = byte_order(big_endian);
+    let mut file = Vec::new();
+    file.extend_from_slice(&u32(0xa1b2c3d4));
+    file.extend_from_slice(&u16(2));
+    file.extend_from_slice(&u16(4));
+    file.extend_from_slice(&u32(0)); // time zone
+    file.extend_from_slice(&u32(0)); // accuracy
+    file.extend_from_slice(&u32(65535));
+    file.extend_from_slice(&u32(LINKTYPE_USBPCAP));
+    for (time, data) in packets() {
+        file.extend_from_slice(&u32((time / 1_000_000) as u32));
+        file.extend_from_slice(&u32((time % 1_000_000) as u32));
+        file.extend_from_slice(&u32(data.len() as u32));
+        file.extend_from_slice(&u32(data.len() as u32));
+        file.extend_from_slice(&data);
+    }
+    file
+}
+
+fn pcapng_file(big_endian: bool) -> Vec<u8> {
+    let (u16, u32) = byte_order(big_endian);
+    let block = |file: &mut Vec<u8>, block_type: u32, body: &[u8]| {
+        let len = 12 + body.len().next_multiple_of(4) as u32;
+        file.extend_from_slice(&u32(block_type));
+        file.extend_from_slice(&u32(len));
+        file.extend_from_slice(body);
+        file.resize(file.len() + body.len().next_multiple_of(4) - body.len(), 0);
+        file.extend_from_slice(&u32(len));
+    };
+
+    let mut file = Vec::new();
+    let mut section = u32(0x1a2b3c4d).to_vec();
+    section.extend_from_slice(&u16(1));
+    section.extend_from_slice(&u16(0));
+    section.extend_from_slice(&[0xff; 8]); // section length unknown
+    block(&mut file, 0x0a0d0d0a, &section);
+
+    let mut interface = u16(LINKTYPE_USBPCAP as u16).to_vec();
+    interface.extend_from_slice(&u16(0));
+    interface.extend_from_slice(&u32(65535));
+    block(&mut file, 1, &interface);
+
+    for (time, data) in packets() {
+        let mut packet = u32(0).to_vec();
+        packet.extend_from_slice(&u32((time >> 32) as u32));
+        packet.extend_from_slice(&u32(time as u32));
+        packet.extend_from_slice(&u32(data.len() as u32));
+        packet.extend_from_slice(&u32(data.len() as u32));
+        packet.extend_from_slice(&data);
+        block(&mut file, 6, &packet);
+    }
+    file
+}
+
+/// Write `bytes` to a temporary file and read its packets.
+fn read_packets(name: &str, bytes: &[u8]) -> Result<Vec<pcap::Packet>, tiny2::Error> {
+    let path = std::env::temp_dir().join(format!("tiny2-{}-{}", std::process::id(), name));
+    std::fs::write(&path, bytes).unwrap();
+    let packets = pcap::read_packets(&path);
+    std::fs::remove_file(&path).unwrap();
+    packets
+}
+
+fn read(name: &str, bytes: &[u8]) -> Vec<ControlRequest> {
+    pcap::control_requests(&read_packets(name, bytes).unwrap()).unwrap()
+}
+
+fn check(requests: &[ControlRequest]) {
+    assert_eq!(requests.len(), 3);
+
+    let set = &requests[0];
+    assert_eq!((set.request, set.unit, set.selector), (SET_CUR, 2, 6));
+    assert_eq!(set.trimmed_data(), command::hdr(true).data);
+    assert_eq!(set.describe().as_deref(), Some("hdr: on"));
+    assert_eq!(set.status, Some(0));
+    assert!((set.time - 1.0).abs() < 1e-9);
+
+    let get = &requests[1];
+    assert_eq!((get.request, get.unit, get.selector), (GET_CUR, 2, 6));
+    assert_eq!(get.data, status_buffer());
+    assert_eq!(get.request_name(), "GET_CUR");
+    assert_eq!(get.status, Some(0));
+    assert!((get.time - 1.5).abs() < 1e-9);
+
+    // The last request never completed.
+    let pending = &requests[2];
+    assert_eq!((pending.request, pending.unit), (SET_CUR, 2));
+    assert_eq!(pending.status, None);
+}
+
+#[test]
+fn reads_pcap() {
+    check(&read("le.pcap", &pcap_file(false)));
+}
+
+#[test]
+fn reads_big_endian_pcap() {
+    check(&read("be.pcap", &pcap_file(true)));
+}
+
+#[test]
+fn reads_pcapng() {
+    check(&read("le.pcapng", &pcapng_file(false)));
+}
+
+#[test]
+fn reads_big_endian_pcapng() {
+    check(&read("be.pcapng", &pcapng_file(true)));
+}
+
+#[test]
+fn rejects_truncated_file() {
+    let mut file = pcapng_file(false);
+    file.truncate(file.len() - 6);
+    let packets = read_packets("cut.pcapng", &file);
+    assert!(packets.is_err());
+}
@@ -0,0 +1,61 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! `explore::sweep` against a replayed trace whose restore calls fail.
+//!
+//! The trace is recorded from the simulator, so the byte map it finds is only
+//! the simulator's own table; what is checked here is that the sweep puts
+//! every setting back and reports the ones it couldn't.
+
+use std::fs;
+use std::time::Duration;
+use tiny2::explore;
+use tiny2::trace::{Op, Record, Trace, TraceReplay};
+use tiny2::Camera;
+
+const V4L2_CID_HFLIP: u32 = 0x00980914;
+const V4L2_CID_VFLIP: u32 = 0x00980915;
+const EIO: i32 = 5;
+
+/// The calls a sweep of the simulator makes, from a fresh simulator.
+fn record_sweep() -> Vec<Record> {
+    let path = std::env::temp_dir().join(format!("tiny2-sweep-{}.jsonl", std::process::id()));
+    let camera = Camera::simulated().with_trace(Trace::create(&path).unwrap());
+    let sweep = explore::sweep(&camera, Duration::ZERO).unwrap();
+    assert!(sweep.is_ok(), "{:?}", sweep);
+    drop(camera);
+
+    let text = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    text.lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+/// Make the last time control `id` was set fail, which is its restore.
+fn fail_last_set(records: &mut [Record], id: u32) {
+    let record = records
+        .iter_mut()
+        .rev()
+        .find(|r| matches!(r.op, Op::SetCtrl { id: i, .. } if i == id))
+        .unwrap();
+    record.errno = Some(EIO);
+}
+
+#[test]
+fn restores_every_setting_past_failures() {
+    let mut records = record_sweep();
+    fail_last_set(&mut records, V4L2_CID_HFLIP);
+    fail_last_set(&mut records, V4L2_CID_VFLIP);
+
+    let camera = Camera::replay(TraceReplay::new(records));
+    let sweep = explore::sweep(&camera, Duration::ZERO).unwrap();
+
+    assert!(sweep.error.is_none());
+    assert!(sweep.probes.iter().any(|p| p.setting == "hdr on"));
+    let unrestored: Vec<&str> = sweep.unrestored.iter().map(|(name, _)| *name).collect();
+    assert_eq!(unrestored, ["mirror", "flip"]);
+    assert!(sweep.unrecoverable.contains(&"fov"));
+    assert!(!sweep.unrecoverable.contains(&"mirror"));
+    assert!(!sweep.is_ok());
+    // Dropping the camera checks that the restores after them were made too.
+}
@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! Replaying traces through `Camera`, without the hardware.
+//!
+//! `fixtures/session.jsonl` is a simulator trace, recorded with
+//! `Camera::simulated().with_trace` while setting the AI mode and HDR,
+//! reading the status, and moving pan. It checks that replay matches calls
+//! and values, not that the bytes are what a real camera sends or answers.
+
+use std::path::{Path, PathBuf};
+use tiny2::trace::TraceReplay;
+use tiny2::{AIMode, Camera, Framing, OBSBotWebCam, TrackingMode, ZoomRatio};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+fn replay(name: &str) -> Camera {
+    Camera::replay(TraceReplay::load(fixture(name)).expect("fixture loads"))
+}
+
+#[test]
+fn replays_recorded_session() {
+    let camera = replay("session.jsonl");
+    camera
+        .set_ai_mode(AIMode::Human(Framing::UpperBody))
+        .unwrap();
+    camera.set_hdr_mode(true).unwrap();
+
+    let status = camera.get_status().unwrap();
+    assert_eq!(status.ai_mode, Some(AIMode::Human(Framing::UpperBody)));
+    assert!(status.hdr_on);
+    assert!(!status.unverified.mirror);
+    assert!(!status.unverified.flip);
+    assert_eq!(
+        status.unverified.tracking_mode,
+        Some(TrackingMode::Headroom)
+    );
+
+    camera.set_pan(36000).unwrap();
+    assert_eq!(camera.get_pan().unwrap(), 36000);
+    assert_eq!(camera.get_zoom_ratio().unwrap(), ZoomRatio(1.0));
+    // Dropping the camera checks that every recorded call was made.
+}
+
+#[test]
+#[should_panic(expected = "doesn't match the trace")]
+fn reports_mismatched_call() {
+    let camera = replay("session.jsonl");
+    // The trace starts with setting the AI mode, not a different one.
+    let _ = camera.set_ai_mode(AIMode::Desk);
+}
+
+#[test]
+#[should_panic(expected = "doesn't match the trace")]
+fn reports_mismatched_control_value() {
+    let camera = replay("session.jsonl");
+    camera
+        .set_ai_mode(AIMode::Human(Framing::UpperBody))
+        .unwrap();
+    camera.set_hdr_mode(true).unwrap();
+    camera.get_status().unwrap();
+    let _ = camera.set_pan(72000);
+}
+
+#[test]
+#[should_panic(expected = "recorded call(s) were never made")]
+fn reports_unused_records() {
+    let camera = replay("session.jsonl");
+    camera
+        .set_ai_mode(AIMode::Human(Framing::UpperBody))
+        .unwrap();
+}
@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! Builders for the SET_CUR requests behind each setting, and a decoder that
+//! turns requests back into descriptions.
+
+use crate::frame::{Frame, AUTO_EXPOSURE, MANUAL_EXPOSURE};
+use crate::xu::{self, XuCommand};
+use crate::{
+    AIMode, ExposureMode, FOVMode, TrackingMode, FRAME_SELECTOR, STATUS_SELECTOR, UVC_GET_CUR,
+    UVC_GET_LEN, UVC_SET_CUR,
+};
+
+/// A SET_CUR request to [`UNIT`](crate::UNIT). `data` isn't padded to
+/// [`BUFFER_LEN`](crate::BUFFER_LEN); the transport does that.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub selector: u8,
+    pub data: Vec<u8>,
+}
+
+impl Request {
+    /// The selector 0x06 command this request sends, if it is a known one.
+    pub fn xu_command(&self) -> Option<&'static XuCommand> {
+        if self.selector == STATUS_SELECTOR {
+            xu::xu_command(*self.data.first()?)
+        } else {
+            None
+        }
+    }
+}
+
+fn xu_request(command: &XuCommand, value: &[u8]) -> Request {
+    Request {
+        selector: STATUS_SELECTOR,
+        data: command.encode(value),
+    }
+}
+
+pub fn ai_mode(mode: AIMode) -> Request {
+    xu_request(&xu::AI_MODE, &mode.to_bytes())
+}
+
+pub fn tracking_mode(mode: TrackingMode) -> Request {
+    xu_request(&xu::TRACKING_MODE, &[mode.to_cmd_value()])
+}
+
+pub fn auto_zoom(on: bool) -> Request {
+    xu_request(&xu::AUTO_ZOOM, &[on.into()])
+}
+
+pub fn target_lock(on: bool) -> Request {
+    xu_request(&xu::TARGET_LOCK, &[on.into()])
+}
+
+pub fn hdr(on: bool) -> Request {
+    xu_request(&xu::HDR, &[on.into()])
+}
+
+pub fn fov(mode: FOVMode) -> Request {
+    xu_request(&xu::FOV, &[mode.to_cmd_value()])
+}
+
+/// Mirror through the extension unit. Unverified; `Camera` uses V4L2 `hflip`.
+pub fn mirror(on: bool) -> Request {
+    xu_request(&xu::MIRROR, &[on.into()])
+}
+
+/// Flip through the extension unit. Unverified; `Camera` uses V4L2 `vflip`.
+pub fn flip(on: bool) -> Request {
+    xu_request(&xu::FLIP, &[on.into()])
+}
+
+/// Face-priority autofocus. Manual focus is a V4L2 control.
+pub fn face_focus(on: bool) -> Request {
+    xu_request(&xu::FACE_FOCUS, &[on.into()])
+}
+
+/// Auto or manual exposure goes to selector 0x02, then for auto, global or
+/// face priority to selector 0x06.
+pub fn exposure_mode(mode: ExposureMode) -> Vec<Request> {
+    let frame = |data: &[u8]| Request {
+        selector: FRAME_SELECTOR,
+        data: data.to_vec(),
+    };
+    match mode {
+        ExposureMode::Manual => vec![frame(&MANUAL_EXPOSURE)],
+        ExposureMode::Global => vec![
+            frame(&AUTO_EXPOSURE),
+            xu_request(&xu::FACE_EXPOSURE, &[0x00]),
+        ],
+        ExposureMode::Face => vec![
+            frame(&AUTO_EXPOSURE),
+            xu_request(&xu::FACE_EXPOSURE, &[0x01]),
+        ],
+    }
+}
+
+/// Whether `data` is the known frame `known`, whatever its sequence number.
+fn is_frame(data: &[u8], known: &[u8]) -> bool {
+    match (Frame::parse(data), Frame::parse(known)) {
+        (Some(a), Some(b)) => a.body == b.body,
+        _ => false,
+    }
+}
+
+/// Describe a request to unit 2 in terms of the commands this crate knows.
+/// Commands whose bytes are guesses are marked unverified. Returns `None`
+/// for anything unknown.
+pub fn describe(request: u8, selector: u8, data: &[u8]) -> Option<String> {
+    match (request, selector) {
+        (UVC_GET_CUR, STATUS_SELECTOR) => Some("read status".to_string()),
+        (UVC_GET_CUR, FRAME_SELECTOR) => Some("read selector 0x02".to_string()),
+        (UVC_GET_LEN, _) => Some("buffer length".to_string()),
+        (UVC_SET_CUR, FRAME_SELECTOR) if is_frame(data, &AUTO_EXPOSURE) => {
+            Some("exposure: auto".to_string())
+        }
+        (UVC_SET_CUR, FRAME_SELECTOR) if is_frame(data, &MANUAL_EXPOSURE) => {
+            Some("exposure: manual".to_string())
+        }
+        (UVC_SET_CUR, STATUS_SELECTOR) if data.len() >= 2 => {
+            let command = xu::xu_command(data[0])?;
+            let value = command.format_value(&data[2..])?;
+            let unverified = if command.is_verified() {
+                ""
+            } else {
+                " (unverified)"
+            };
+            Some(format!("{}: {}{}", command.name, value, unverified))
+        }
+        _ => None,
+    }
+}
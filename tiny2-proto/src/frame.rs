@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! Framing of selector 0x02 messages.
+//!
+//! A message is `aa 25 <sequence> <length> <body>`, with the sequence number
+//! and body length as little-endian `u16`s. The body carries what look like
+//! checksums that aren't understood yet, so new messages can't be built; the
+//! ones below were recorded from OBSBOT Center.
+
+use crate::Error;
+
+/// The first two bytes of every frame.
+pub const MAGIC: [u8; 2] = [0xaa, 0x25];
+
+/// Switch to automatic exposure.
+pub const AUTO_EXPOSURE: [u8; 18] = [
+    0xaa, 0x25, 0x16, 0x00, 0x0c, 0x00, 0x58, 0x91, 0x0a, 0x02, 0x82, 0x29, 0x05, 0x00, 0xb2, 0xaf,
+    0x02, 0x04,
+];
+
+/// Switch to manual exposure.
+pub const MANUAL_EXPOSURE: [u8; 18] = [
+    0xaa, 0x25, 0x15, 0x00, 0x0c, 0x00, 0xa8, 0x9e, 0x0a, 0x02, 0x82, 0x29, 0x05, 0x00, 0xf9, 0x27,
+    0x01, 0x32,
+];
+
+/// A selector 0x02 message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub sequence: u16,
+    pub body: Vec<u8>,
+}
+
+impl Frame {
+    /// Parse a frame from the start of `data`, ignoring anything after the
+    /// body, such as the zero padding of a SET_CUR buffer.
+    pub fn parse(data: &[u8]) -> Option<Frame> {
+        if data.get(..2)? != MAGIC {
+            return None;
+        }
+        let sequence = u16::from_le_bytes(data.get(2..4)?.try_into().ok()?);
+        let length = u16::from_le_bytes(data.get(4..6)?.try_into().ok()?) as usize;
+        Some(Frame {
+            sequence,
+            body: data.get(6..6 + length)?.to_vec(),
+        })
+    }
+
+    /// The frame's bytes. Fails if the body is too long for its `u16`
+    /// length.
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        let length =
+            u16::try_from(self.body.len()).map_err(|_| Error::FrameTooLong(self.body.len()))?;
+        let mut data = MAGIC.to_vec();
+        data.extend_from_slice(&self.sequence.to_le_bytes());
+        data.extend_from_slice(&length.to_le_bytes());
+        data.extend_from_slice(&self.body);
+        Ok(data)
+    }
+}
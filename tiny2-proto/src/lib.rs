@@ -0,0 +1,604 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! The OBSBOT Tiny 2 control protocol, without any IO.
+//!
+//! Everything here is plain data: how settings are encoded as UVC
+//! extension-unit requests, and how the status block decodes. The V4L2
+//! transport lives in the `tiny2` crate; a libusb-based tool or a test can use
+//! this crate on its own.
+//!
+//! All requests go to extension unit [`UNIT`]. Selector 0x06 takes
+//! `[command, len, value...]` commands (see [`xu`]) and reads back as the
+//! 60-byte status block decoded by [`CameraStatus`]. Selector 0x02 takes
+//! framed messages (see [`frame`]).
+
+pub mod command;
+pub mod frame;
+pub mod xu;
+
+use std::{fmt::Display, str::FromStr};
+use thiserror::Error;
+use xu::XuCommand;
+
+/// The extension unit all vendor requests go to.
+pub const UNIT: u8 = 0x02;
+/// The selector taking `[command, len, value...]` commands and reading back
+/// the status block.
+pub const STATUS_SELECTOR: u8 = 0x06;
+/// The selector taking framed messages.
+pub const FRAME_SELECTOR: u8 = 0x02;
+/// Length of the GET_CUR/SET_CUR buffer of both selectors.
+pub const BUFFER_LEN: usize = 60;
+
+/// UVC request codes (bRequest).
+pub const UVC_SET_CUR: u8 = 0x01;
+pub const UVC_GET_CUR: u8 = 0x81;
+pub const UVC_GET_LEN: u8 = 0x85;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum Error {
+    #[error("value of {1} is not supported for {0}")]
+    UnsupportedIntValue(String, i32),
+    #[error("\"{1}\" is not a valid {0}")]
+    UnsupportedName(String, String),
+    #[error("unknown {0} in status: {1:#04x} {2:#04x}")]
+    UnknownStatus(&'static str, u8, u8),
+    #[error("status block of {0} bytes is shorter than {1}")]
+    StatusTooShort(usize, usize),
+    #[error("frame body of {0} bytes is longer than 65535")]
+    FrameTooLong(usize),
+}
+
+/// Find the value in `all` whose display name matches `s`, ignoring case and
+/// punctuation, so "Upper Body", "upper-body" and "upper_body" all match.
+pub fn parse_name<T: Copy + Display>(s: &str, all: &[T], type_name: &str) -> Result<T, Error> {
+    let normalize = |s: &str| -> String {
+        s.chars()
+            .filter(|c| c.is_alphanumeric())
+            .map(|c| c.to_ascii_lowercase())
+            .collect()
+    };
+    let wanted = normalize(s);
+    all.iter()
+        .find(|v| normalize(&v.to_string()) == wanted)
+        .copied()
+        .ok_or_else(|| Error::UnsupportedName(type_name.to_string(), s.to_string()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CameraStatus {
+    /// `None` if the camera reports an AI mode this crate doesn't know.
+    pub ai_mode: Option<AIMode>,
+    pub hdr_on: bool,
+    /// Fields read from status offsets nobody has confirmed on a device.
+    pub unverified: UnverifiedStatus,
+}
+
+/// The status fields of [`Provenance::Unverified`](xu::Provenance::Unverified)
+/// commands. The offsets they are read from are guesses, so these may not
+/// mean what their names say.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnverifiedStatus {
+    pub face_focus: bool,
+    pub mirror: bool,
+    pub flip: bool,
+    /// `None` if the byte isn't a tracking mode this crate knows; see
+    /// [`CameraStatus::decode_tracking_mode`] for its value.
+    pub tracking_mode: Option<TrackingMode>,
+    pub auto_zoom: bool,
+    pub target_lock: bool,
+}
+
+impl CameraStatus {
+    /// Decode a status block. The offsets come from [`xu::XU_COMMANDS`];
+    /// those of unverified commands are guesses and decode into
+    /// [`CameraStatus::unverified`]. Fails if the block is shorter than
+    /// [`xu::STATUS_LEN`].
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        Self::check_len(bytes)?;
+        let byte = |command: &XuCommand| bytes[command.status[0]];
+        let flag = |command: &XuCommand| byte(command) != 0;
+
+        let ai_mode = Self::decode_ai_mode(bytes).ok();
+
+        let hdr_on = flag(&xu::HDR);
+        let face_focus = flag(&xu::FACE_FOCUS);
+        let mirror = flag(&xu::MIRROR);
+        let flip = flag(&xu::FLIP);
+        let tracking_mode = Self::decode_tracking_mode(bytes).ok();
+        let auto_zoom = flag(&xu::AUTO_ZOOM);
+        let target_lock = flag(&xu::TARGET_LOCK);
+
+        Ok(CameraStatus {
+            ai_mode,
+            hdr_on,
+            unverified: UnverifiedStatus {
+                face_focus,
+                mirror,
+                flip,
+                tracking_mode,
+                auto_zoom,
+                target_lock,
+            },
+        })
+    }
+
+    /// Decode just the AI mode of a status block, failing on unknown modes.
+    pub fn decode_ai_mode(bytes: &[u8]) -> Result<AIMode, Error> {
+        Self::check_len(bytes)?;
+        let [m, n] = [0, 1].map(|i| bytes[xu::AI_MODE.status[i]]);
+        AIMode::from_bytes([m, n]).ok_or(Error::UnknownStatus("AI mode", m, n))
+    }
+
+    /// Decode just the tracking mode of a status block, failing with the
+    /// byte's value on unknown modes. The offset is unverified.
+    pub fn decode_tracking_mode(bytes: &[u8]) -> Result<TrackingMode, Error> {
+        Self::check_len(bytes)?;
+        TrackingMode::try_from(bytes[xu::TRACKING_MODE.status[0]] as i32)
+    }
+
+    fn check_len(bytes: &[u8]) -> Result<(), Error> {
+        if bytes.len() < xu::STATUS_LEN {
+            return Err(Error::StatusTooShort(bytes.len(), xu::STATUS_LEN));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum ExposureMode {
+    Manual,
+    Global,
+    Face,
+}
+
+impl ExposureMode {
+    pub const ALL: [ExposureMode; 3] = [
+        ExposureMode::Manual,
+        ExposureMode::Global,
+        ExposureMode::Face,
+    ];
+}
+
+impl Display for ExposureMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExposureMode::Manual => write!(f, "Manual"),
+            ExposureMode::Global => write!(f, "Global"),
+            ExposureMode::Face => write!(f, "Face"),
+        }
+    }
+}
+
+impl FromStr for ExposureMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_name(s, &ExposureMode::ALL, "ExposureMode")
+    }
+}
+
+/// Framing used by human tracking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Framing {
+    Standard,
+    UpperBody,
+    CloseUp,
+    Headless,
+    LowerBody,
+}
+
+impl Framing {
+    pub const ALL: [Framing; 5] = [
+        Framing::Standard,
+        Framing::UpperBody,
+        Framing::CloseUp,
+        Framing::Headless,
+        Framing::LowerBody,
+    ];
+}
+
+impl Display for Framing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Framing::Standard => write!(f, "Standard"),
+            Framing::UpperBody => write!(f, "Upper Body"),
+            Framing::CloseUp => write!(f, "Close-up"),
+            Framing::Headless => write!(f, "Headless"),
+            Framing::LowerBody => write!(f, "Lower Body"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum AIMode {
+    NoTracking,
+    Human(Framing),
+    Desk,
+    Whiteboard,
+    Hand,
+    Group,
+}
+
+/// Every AI mode with its `(m, n)` bytes in the 0x16 command and status block,
+/// and its display name. The order is the integer numbering used by OSC.
+const AI_MODES: [(AIMode, [u8; 2], &str); 10] = [
+    (AIMode::NoTracking, [0, 0], "No Tracking"),
+    (AIMode::Human(Framing::Standard), [2, 0], "Normal Tracking"),
+    (AIMode::Human(Framing::UpperBody), [2, 1], "Upper Body"),
+    (AIMode::Human(Framing::CloseUp), [2, 2], "Close-up"),
+    (AIMode::Human(Framing::Headless), [2, 3], "Headless"),
+    (AIMode::Human(Framing::LowerBody), [2, 4], "Lower Body"),
+    (AIMode::Desk, [5, 0], "Desk"),
+    (AIMode::Whiteboard, [4, 0], "Whiteboard"),
+    (AIMode::Hand, [3, 0], "Hand"),
+    (AIMode::Group, [1, 0], "Group"),
+];
+
+/// Other `(m, n)` bytes that decode as a mode but are never sent. The status
+/// decoder used to read Hand as (6, 0) while the setter sent (3, 0); until a
+/// device settles which one it reports, both are accepted.
+const AI_MODE_ALIASES: [(AIMode, [u8; 2]); 1] = [(AIMode::Hand, [6, 0])];
+
+#[allow(non_upper_case_globals)]
+impl AIMode {
+    pub const ALL: [AIMode; AI_MODES.len()] = {
+        let mut all = [AIMode::NoTracking; AI_MODES.len()];
+        let mut i = 0;
+        while i < AI_MODES.len() {
+            all[i] = AI_MODES[i].0;
+            i += 1;
+        }
+        all
+    };
+
+    #[deprecated(note = "use AIMode::Human(Framing::Standard)")]
+    pub const NormalTracking: AIMode = AIMode::Human(Framing::Standard);
+    #[deprecated(note = "use AIMode::Human(Framing::UpperBody)")]
+    pub const UpperBody: AIMode = AIMode::Human(Framing::UpperBody);
+    #[deprecated(note = "use AIMode::Human(Framing::CloseUp)")]
+    pub const CloseUp: AIMode = AIMode::Human(Framing::CloseUp);
+    #[deprecated(note = "use AIMode::Human(Framing::Headless)")]
+    pub const Headless: AIMode = AIMode::Human(Framing::Headless);
+    #[deprecated(note = "use AIMode::Human(Framing::LowerBody)")]
+    pub const LowerBody: AIMode = AIMode::Human(Framing::LowerBody);
+    #[deprecated(note = "use AIMode::Desk")]
+    pub const DeskMode: AIMode = AIMode::Desk;
+
+    fn entry(self) -> &'static (AIMode, [u8; 2], &'static str) {
+        AI_MODES
+            .iter()
+            .find(|(mode, _, _)| *mode == self)
+            .expect("every AIMode is in AI_MODES")
+    }
+
+    /// The `(m, n)` bytes used for this mode by the 0x16 command and the status block.
+    pub fn to_bytes(self) -> [u8; 2] {
+        self.entry().1
+    }
+
+    /// The mode with these `(m, n)` bytes, also accepting the encodings in
+    /// `AI_MODE_ALIASES`.
+    pub fn from_bytes(bytes: [u8; 2]) -> Option<AIMode> {
+        AI_MODES
+            .iter()
+            .map(|&(mode, b, _)| (mode, b))
+            .chain(AI_MODE_ALIASES)
+            .find(|(_, b)| *b == bytes)
+            .map(|(mode, _)| mode)
+    }
+}
+
+impl Display for AIMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.entry().2)
+    }
+}
+
+impl FromStr for AIMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_name(s, &AIMode::ALL, "AIMode")
+    }
+}
+
+impl TryFrom<i32> for AIMode {
+    type Error = Error;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        usize::try_from(value)
+            .ok()
+            .and_then(|i| AIMode::ALL.get(i).copied())
+            .ok_or_else(|| Error::UnsupportedIntValue("AIMode".to_string(), value))
+    }
+}
+
+/// Framing used while tracking: extra headroom above the subject, the
+/// standard framing, or faster motion tracking.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum TrackingMode {
+    Headroom,
+    Standard,
+    Motion,
+}
+
+impl TrackingMode {
+    pub const ALL: [TrackingMode; 3] = [
+        TrackingMode::Headroom,
+        TrackingMode::Standard,
+        TrackingMode::Motion,
+    ];
+
+    fn to_cmd_value(self) -> u8 {
+        match self {
+            TrackingMode::Headroom => 0,
+            TrackingMode::Standard => 1,
+            TrackingMode::Motion => 2,
+        }
+    }
+}
+
+impl Display for TrackingMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrackingMode::Headroom => write!(f, "Headroom"),
+            TrackingMode::Standard => write!(f, "Standard"),
+            TrackingMode::Motion => write!(f, "Motion"),
+        }
+    }
+}
+
+impl FromStr for TrackingMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_name(s, &TrackingMode::ALL, "TrackingMode")
+    }
+}
+
+impl TryFrom<i32> for TrackingMode {
+    type Error = Error;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(TrackingMode::Headroom),
+            1 => Ok(TrackingMode::Standard),
+            2 => Ok(TrackingMode::Motion),
+            _ => Err(Error::UnsupportedIntValue(
+                "TrackingMode".to_string(),
+                value,
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum FOVMode {
+    Wide,   // 86°
+    Normal, // 78°
+    Narrow, // 65°
+}
+
+impl FOVMode {
+    pub const ALL: [FOVMode; 3] = [FOVMode::Wide, FOVMode::Normal, FOVMode::Narrow];
+
+    fn to_cmd_value(self) -> u8 {
+        match self {
+            FOVMode::Wide => 1,
+            FOVMode::Normal => 2,
+            FOVMode::Narrow => 3,
+        }
+    }
+}
+
+impl Display for FOVMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FOVMode::Wide => write!(f, "Wide"),
+            FOVMode::Normal => write!(f, "Normal"),
+            FOVMode::Narrow => write!(f, "Narrow"),
+        }
+    }
+}
+
+impl FromStr for FOVMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_name(s, &FOVMode::ALL, "FOVMode")
+    }
+}
+
+/// Focus behaviour: continuous autofocus, autofocus prioritising faces, or
+/// manual focus via `set_focus`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum FocusMode {
+    Auto,
+    Face,
+    Manual,
+}
+
+impl FocusMode {
+    pub const ALL: [FocusMode; 3] = [FocusMode::Auto, FocusMode::Face, FocusMode::Manual];
+}
+
+impl FromStr for FocusMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_name(s, &FocusMode::ALL, "FocusMode")
+    }
+}
+
+impl Display for FocusMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FocusMode::Auto => write!(f, "Auto"),
+            FocusMode::Face => write!(f, "Face"),
+            FocusMode::Manual => write!(f, "Manual"),
+        }
+    }
+}
+
+/// Anti-flicker setting, matching the V4L2 `power_line_frequency` menu.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum PowerLineFrequency {
+    Disabled,
+    #[cfg_attr(feature = "serde", serde(rename = "50hz"))]
+    Hz50,
+    #[cfg_attr(feature = "serde", serde(rename = "60hz"))]
+    Hz60,
+    Auto,
+}
+
+impl PowerLineFrequency {
+    pub const ALL: [PowerLineFrequency; 4] = [
+        PowerLineFrequency::Disabled,
+        PowerLineFrequency::Hz50,
+        PowerLineFrequency::Hz60,
+        PowerLineFrequency::Auto,
+    ];
+
+    /// The index of this setting in the V4L2 `power_line_frequency` menu.
+    pub fn to_ctrl_value(self) -> i32 {
+        match self {
+            PowerLineFrequency::Disabled => 0,
+            PowerLineFrequency::Hz50 => 1,
+            PowerLineFrequency::Hz60 => 2,
+            PowerLineFrequency::Auto => 3,
+        }
+    }
+}
+
+impl Display for PowerLineFrequency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PowerLineFrequency::Disabled => write!(f, "Disabled"),
+            PowerLineFrequency::Hz50 => write!(f, "50 Hz"),
+            PowerLineFrequency::Hz60 => write!(f, "60 Hz"),
+            PowerLineFrequency::Auto => write!(f, "Auto"),
+        }
+    }
+}
+
+impl FromStr for PowerLineFrequency {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_name(s, &PowerLineFrequency::ALL, "PowerLineFrequency")
+    }
+}
+
+impl TryFrom<i32> for PowerLineFrequency {
+    type Error = Error;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PowerLineFrequency::Disabled),
+            1 => Ok(PowerLineFrequency::Hz50),
+            2 => Ok(PowerLineFrequency::Hz60),
+            3 => Ok(PowerLineFrequency::Auto),
+            _ => Err(Error::UnsupportedIntValue(
+                "PowerLineFrequency".to_string(),
+                value,
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every value's display name parses back to it.
+    fn round_trip<T>(all: &[T])
+    where
+        T: Copy + Display + FromStr<Err = Error> + PartialEq + std::fmt::Debug,
+    {
+        for &value in all {
+            assert_eq!(value.to_string().parse::<T>().unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn names_round_trip() {
+        round_trip(&ExposureMode::ALL);
+        round_trip(&AIMode::ALL);
+        round_trip(&TrackingMode::ALL);
+        round_trip(&FOVMode::ALL);
+        round_trip(&FocusMode::ALL);
+        round_trip(&PowerLineFrequency::ALL);
+    }
+
+    #[test]
+    fn frame_round_trip() {
+        let frame = frame::Frame::parse(&frame::AUTO_EXPOSURE).unwrap();
+        assert_eq!(frame.encode().unwrap(), frame::AUTO_EXPOSURE);
+
+        let long = frame::Frame {
+            sequence: 1,
+            body: vec![0; u16::MAX as usize + 1],
+        };
+        assert!(matches!(long.encode(), Err(Error::FrameTooLong(65536))));
+    }
+
+    #[test]
+    fn short_status_is_an_error() {
+        let bytes = [0u8; xu::STATUS_LEN - 1];
+        assert!(matches!(
+            CameraStatus::decode(&bytes),
+            Err(Error::StatusTooShort(n, xu::STATUS_LEN)) if n == bytes.len()
+        ));
+        assert!(CameraStatus::decode(&[0u8; xu::STATUS_LEN]).is_ok());
+    }
+
+    #[test]
+    fn unknown_tracking_mode_keeps_its_value() {
+        let mut bytes = [0u8; xu::STATUS_LEN];
+        bytes[xu::TRACKING_MODE.status[0]] = 7;
+        let status = CameraStatus::decode(&bytes).unwrap();
+        assert_eq!(status.unverified.tracking_mode, None);
+        assert!(matches!(
+            CameraStatus::decode_tracking_mode(&bytes),
+            Err(Error::UnsupportedIntValue(_, 7))
+        ));
+    }
+}
@@ -0,0 +1,222 @@
+// SPDX-License-Identifier: EUPL-1.2
+
+//! The selector 0x06 commands: command byte, value length, and where the
+//! value reads back in the status block.
+//!
+//! Not every entry has been checked against a device; see [`Provenance`].
+
+use crate::{AIMode, ExposureMode, FOVMode, TrackingMode};
+
+/// Where an [`XuCommand`]'s bytes come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provenance {
+    /// Sent, and read back where there is a status offset, by the setters
+    /// and status decoder this crate started out with.
+    Original,
+    /// Not yet seen on a device: the command byte and status offsets are
+    /// guesses. `obsbot-ctl sweep` shows which status bytes a setting moves.
+    Unverified,
+}
+
+/// A selector 0x06 command, sent as `[command, len, value...]`.
+#[derive(Debug, Clone, Copy)]
+pub struct XuCommand {
+    pub name: &'static str,
+    pub command: u8,
+    /// Number of value bytes.
+    pub len: u8,
+    /// Status block offsets the value bytes read back from, if known.
+    pub status: &'static [usize],
+    pub provenance: Provenance,
+    format: fn(&[u8]) -> Option<String>,
+}
+
+impl XuCommand {
+    pub fn is_verified(&self) -> bool {
+        self.provenance != Provenance::Unverified
+    }
+
+    /// The command bytes that set `value`.
+    pub fn encode(&self, value: &[u8]) -> Vec<u8> {
+        debug_assert_eq!(value.len(), self.len as usize, "{} value length", self.name);
+        let mut cmd = vec![self.command, self.len];
+        cmd.extend_from_slice(value);
+        cmd
+    }
+
+    /// Describe the value bytes of a command, e.g. "on" or "Desk". `None` if
+    /// they aren't a value this crate knows.
+    pub fn format_value(&self, value: &[u8]) -> Option<String> {
+        if value.len() < self.len as usize {
+            return None;
+        }
+        (self.format)(&value[..self.len as usize])
+    }
+}
+
+fn format_on_off(value: &[u8]) -> Option<String> {
+    Some(if value[0] != 0 { "on" } else { "off" }.to_string())
+}
+
+/// From the original `set_hdr_mode` and the status decoder's HDR byte.
+pub const HDR: XuCommand = XuCommand {
+    name: "hdr",
+    command: 0x01,
+    len: 1,
+    status: &[0x06],
+    provenance: Provenance::Original,
+    format: format_on_off,
+};
+
+/// Face-priority auto exposure, 0 for global and 1 for face. Only the
+/// selector 0x06 half of [`ExposureMode`]; auto/manual is set on 0x02.
+/// From the original `set_exposure_mode`; where it reads back is unknown.
+pub const FACE_EXPOSURE: XuCommand = XuCommand {
+    name: "face-exposure",
+    command: 0x03,
+    len: 1,
+    status: &[],
+    provenance: Provenance::Original,
+    format: |v| match v[0] {
+        0 => Some(ExposureMode::Global.to_string()),
+        1 => Some(ExposureMode::Face.to_string()),
+        _ => None,
+    },
+};
+
+/// From the original `set_fov`; where it reads back is unknown.
+pub const FOV: XuCommand = XuCommand {
+    name: "fov",
+    command: 0x04,
+    len: 1,
+    status: &[],
+    provenance: Provenance::Original,
+    format: |v| {
+        FOVMode::ALL
+            .into_iter()
+            .find(|m| m.to_cmd_value() == v[0])
+            .map(|m| m.to_string())
+    },
+};
+
+/// Unverified: 0x0a and 0x0b were picked for mirror and flip, and 0x2c and
+/// 0x2d for their readback, without a capture of either. Cameras with the
+/// V4L2 `horizontal_flip` and `vertical_flip` controls use those instead.
+pub const MIRROR: XuCommand = XuCommand {
+    name: "mirror",
+    command: 0x0a,
+    len: 1,
+    status: &[0x2c],
+    provenance: Provenance::Unverified,
+    format: format_on_off,
+};
+
+/// Unverified; see [`MIRROR`].
+pub const FLIP: XuCommand = XuCommand {
+    name: "flip",
+    command: 0x0b,
+    len: 1,
+    status: &[0x2d],
+    provenance: Provenance::Unverified,
+    format: format_on_off,
+};
+
+/// The `(m, n)` bytes of [`AIMode::to_bytes`]. From the original
+/// `set_ai_mode` and status decoder, which disagreed only on Hand.
+pub const AI_MODE: XuCommand = XuCommand {
+    name: "ai-mode",
+    command: 0x16,
+    len: 2,
+    status: &[0x18, 0x1c],
+    provenance: Provenance::Original,
+    format: |v| AIMode::from_bytes([v[0], v[1]]).map(|m| m.to_string()),
+};
+
+/// Unverified, like [`AUTO_ZOOM`] and [`TARGET_LOCK`]: no capture backs
+/// 0x17, 0x19 and 0x1a or their readback at 0x1d, 0x20 and 0x22.
+pub const TRACKING_MODE: XuCommand = XuCommand {
+    name: "tracking-mode",
+    command: 0x17,
+    len: 1,
+    status: &[0x1d],
+    provenance: Provenance::Unverified,
+    format: |v| {
+        TrackingMode::try_from(v[0] as i32)
+            .ok()
+            .map(|m| m.to_string())
+    },
+};
+
+/// Unverified; see [`TRACKING_MODE`].
+pub const AUTO_ZOOM: XuCommand = XuCommand {
+    name: "auto-zoom",
+    command: 0x19,
+    len: 1,
+    status: &[0x20],
+    provenance: Provenance::Unverified,
+    format: format_on_off,
+};
+
+/// Unverified; see [`TRACKING_MODE`].
+pub const TARGET_LOCK: XuCommand = XuCommand {
+    name: "target-lock",
+    command: 0x1a,
+    len: 1,
+    status: &[0x22],
+    provenance: Provenance::Unverified,
+    format: format_on_off,
+};
+
+/// Face-priority autofocus on or off. Switching between autofocus and
+/// manual focus is a separate V4L2 control, `focus_automatic_continuous`.
+/// Unverified: neither 0x25 nor the 0x2a readback has been seen on a device.
+pub const FACE_FOCUS: XuCommand = XuCommand {
+    name: "face-focus",
+    command: 0x25,
+    len: 1,
+    status: &[0x2a],
+    provenance: Provenance::Unverified,
+    format: format_on_off,
+};
+
+/// Every known selector 0x06 command.
+pub const XU_COMMANDS: [XuCommand; 10] = [
+    HDR,
+    FACE_EXPOSURE,
+    FOV,
+    MIRROR,
+    FLIP,
+    AI_MODE,
+    TRACKING_MODE,
+    AUTO_ZOOM,
+    TARGET_LOCK,
+    FACE_FOCUS,
+];
+
+/// The shortest status block that holds the status bytes of every command.
+pub const STATUS_LEN: usize = {
+    let mut len = 0;
+    let mut i = 0;
+    while i < XU_COMMANDS.len() {
+        let status = XU_COMMANDS[i].status;
+        let mut j = 0;
+        while j < status.len() {
+            if status[j] + 1 > len {
+                len = status[j] + 1;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    len
+};
+
+/// The selector 0x06 command with command byte `command`.
+pub fn xu_command(command: u8) -> Option<&'static XuCommand> {
+    XU_COMMANDS.iter().find(|c| c.command == command)
+}
+
+/// The command whose value reads back at `offset` of the status block.
+pub fn status_command_at(offset: usize) -> Option<&'static XuCommand> {
+    XU_COMMANDS.iter().find(|c| c.status.contains(&offset))
+}